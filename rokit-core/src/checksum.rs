@@ -0,0 +1,265 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::common;
+use crate::rokit_error::RokitError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ChecksumAlgorithm {
+    #[default]
+    None,
+    Sum8,
+    Xor8,
+    Crc8,
+    Crc16Modbus,
+    Crc16Ccitt,
+    Crc32,
+}
+
+impl ChecksumAlgorithm {
+    pub const ALL: [ChecksumAlgorithm; 7] = [ChecksumAlgorithm::None, ChecksumAlgorithm::Sum8, ChecksumAlgorithm::Xor8, ChecksumAlgorithm::Crc8,
+        ChecksumAlgorithm::Crc16Modbus, ChecksumAlgorithm::Crc16Ccitt, ChecksumAlgorithm::Crc32];
+
+    pub fn from_name(name:&str) -> Option<ChecksumAlgorithm> {
+        let name : String = name.chars().filter(|c| *c != '-' && *c != '_' && *c != '/').collect::<String>().to_lowercase();
+        match name.as_str() {
+            "none" => Some(ChecksumAlgorithm::None),
+            "sum8" => Some(ChecksumAlgorithm::Sum8),
+            "xor" | "xor8" => Some(ChecksumAlgorithm::Xor8),
+            "crc8" => Some(ChecksumAlgorithm::Crc8),
+            "crc16modbus" | "modbus" => Some(ChecksumAlgorithm::Crc16Modbus),
+            "crc16ccitt" | "ccitt" => Some(ChecksumAlgorithm::Crc16Ccitt),
+            "crc32" => Some(ChecksumAlgorithm::Crc32),
+            _ => None
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        match self {
+            ChecksumAlgorithm::None => 0,
+            ChecksumAlgorithm::Sum8 | ChecksumAlgorithm::Xor8 | ChecksumAlgorithm::Crc8 => 1,
+            ChecksumAlgorithm::Crc16Modbus | ChecksumAlgorithm::Crc16Ccitt => 2,
+            ChecksumAlgorithm::Crc32 => 4,
+        }
+    }
+
+    // CRC-8 is poly 0x07 init 0, CRC-16/CCITT is poly 0x1021 init 0xFFFF (CCITT-FALSE),
+    // CRC-32 is the IEEE 802.3 CRC.
+    pub fn compute(&self, data:&[u8]) -> u32 {
+        match self {
+            ChecksumAlgorithm::None => 0,
+            ChecksumAlgorithm::Sum8 => data.iter().fold(0u8, |sum, b| sum.wrapping_add(*b)) as u32,
+            ChecksumAlgorithm::Xor8 => data.iter().fold(0u8, |sum, b| sum ^ *b) as u32,
+            ChecksumAlgorithm::Crc8 => {
+                let mut crc = 0u8;
+                for byte in data {
+                    crc ^= *byte;
+                    for _ in 0..8 {
+                        crc = if crc & 0x80 != 0 { (crc << 1) ^ 0x07 } else { crc << 1 };
+                    }
+                }
+                crc as u32
+            },
+            ChecksumAlgorithm::Crc16Modbus => {
+                let mut crc = 0xFFFFu16;
+                for byte in data {
+                    crc ^= *byte as u16;
+                    for _ in 0..8 {
+                        crc = if crc & 0x0001 != 0 { (crc >> 1) ^ 0xA001 } else { crc >> 1 };
+                    }
+                }
+                crc as u32
+            },
+            ChecksumAlgorithm::Crc16Ccitt => {
+                let mut crc = 0xFFFFu16;
+                for byte in data {
+                    crc ^= (*byte as u16) << 8;
+                    for _ in 0..8 {
+                        crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x1021 } else { crc << 1 };
+                    }
+                }
+                crc as u32
+            },
+            ChecksumAlgorithm::Crc32 => {
+                let mut crc = 0xFFFF_FFFFu32;
+                for byte in data {
+                    crc ^= *byte as u32;
+                    for _ in 0..8 {
+                        crc = if crc & 0x0000_0001 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+                    }
+                }
+                !crc
+            }
+        }
+    }
+
+    // CRC-16/Modbus goes low byte first as on the wire, the others big endian.
+    pub fn to_bytes(&self, value:u32) -> Vec<u8> {
+        match self {
+            ChecksumAlgorithm::Crc16Modbus => (value as u16).to_le_bytes().to_vec(),
+            _ => value.to_be_bytes()[4 - self.width()..].to_vec()
+        }
+    }
+}
+
+impl fmt::Display for ChecksumAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ChecksumAlgorithm::None => "无校验",
+            ChecksumAlgorithm::Sum8 => "SUM8",
+            ChecksumAlgorithm::Xor8 => "XOR",
+            ChecksumAlgorithm::Crc8 => "CRC-8",
+            ChecksumAlgorithm::Crc16Modbus => "CRC-16/Modbus",
+            ChecksumAlgorithm::Crc16Ccitt => "CRC-16/CCITT",
+            ChecksumAlgorithm::Crc32 => "CRC-32",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChecksumResult {
+    Ok,
+    Mismatch(Vec<u8>, Vec<u8>),
+    TooShort,
+}
+
+// The checksum covers the payload without its first `skip_start` and last
+// `skip_end` bytes and is placed after the payload, in front of a trailing
+// line ending or frame delimiter.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Checksum {
+    pub algorithm:ChecksumAlgorithm,
+    pub skip_start:usize,
+    pub skip_end:usize,
+    pub append:bool,
+    pub verify:bool,
+}
+
+impl Checksum {
+    // <算法>[:跳过开头[:跳过结尾]]
+    pub fn parse_spec(spec:&str) -> Result<Checksum, RokitError> {
+        let mut parts = spec.split(':');
+        let algorithm = match ChecksumAlgorithm::from_name(parts.next().unwrap_or("")) {
+            Some(x) => x,
+            None => return Err(RokitError::decode("未知校验算法:".to_string() + spec))
+        };
+        let mut checksum = Checksum{ algorithm, append:true, verify:true, ..Checksum::default() };
+        checksum.set_range(&parts.collect::<Vec<&str>>().join(":"))?;
+        Ok(checksum)
+    }
+
+    pub fn range(&self) -> String {
+        format!("{}:{}", self.skip_start, self.skip_end)
+    }
+
+    pub fn set_range(&mut self, range:&str) -> Result<(), RokitError> {
+        let range = range.trim();
+        if range.is_empty() {
+            self.skip_start = 0;
+            self.skip_end = 0;
+            return Ok(());
+        }
+        let (start, end) = match range.split_once(':') {
+            Some((start, end)) => (start, end),
+            None => (range, "0")
+        };
+        match (start.trim().parse::<usize>(), end.trim().parse::<usize>()) {
+            (Ok(start), Ok(end)) => {
+                self.skip_start = start;
+                self.skip_end = end;
+                Ok(())
+            },
+            _ => Err(RokitError::decode("校验范围格式错误:".to_string() + range))
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.algorithm != ChecksumAlgorithm::None
+    }
+
+    fn covered<'a>(&self, data:&'a [u8]) -> Option<&'a [u8]> {
+        if self.skip_start + self.skip_end > data.len() {
+            return None;
+        }
+        Some(&data[self.skip_start..data.len() - self.skip_end])
+    }
+
+    pub fn checksum(&self, payload:&[u8]) -> Result<Vec<u8>, RokitError> {
+        match self.covered(payload) {
+            Some(data) => Ok(self.algorithm.to_bytes(self.algorithm.compute(data))),
+            None => Err(RokitError::decode(format!("数据长度{}小于校验范围{}", payload.len(), self.range())))
+        }
+    }
+
+    // Inserts the checksum in front of the last `trailer` bytes of the buffer
+    // and returns it.
+    pub fn insert(&self, buffer:&mut Vec<u8>, trailer:usize) -> Result<Vec<u8>, RokitError> {
+        let end = buffer.len().saturating_sub(trailer);
+        let sum = self.checksum(&buffer[..end])?;
+        buffer.splice(end..end, sum.iter().cloned());
+        Ok(sum)
+    }
+
+    // `trailer` is removed from the end of the frame before the checksum is read.
+    pub fn verify(&self, frame:&[u8], trailer:&[u8]) -> ChecksumResult {
+        let frame = frame.strip_suffix(trailer).unwrap_or(frame);
+        let width = self.algorithm.width();
+        if frame.len() < width {
+            return ChecksumResult::TooShort;
+        }
+        let (payload, received) = frame.split_at(frame.len() - width);
+        match self.checksum(payload) {
+            Ok(expected) if expected == received => ChecksumResult::Ok,
+            Ok(expected) => ChecksumResult::Mismatch(received.to_vec(), expected),
+            Err(_) => ChecksumResult::TooShort
+        }
+    }
+
+    pub fn describe(&self, result:&ChecksumResult) -> Option<String> {
+        match result {
+            ChecksumResult::Ok => None,
+            ChecksumResult::Mismatch(received, expected) => Some(format!("{}校验错误:收到{} 计算{}", self.algorithm,
+                common::bytes_to_hex(received), common::bytes_to_hex(expected))),
+            ChecksumResult::TooShort => Some(format!("{}校验错误:数据太短", self.algorithm))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHECK : &[u8] = b"123456789";
+
+    #[test]
+    fn check_values() {
+        assert_eq!(ChecksumAlgorithm::Crc8.compute(CHECK), 0xF4);
+        assert_eq!(ChecksumAlgorithm::Crc16Modbus.compute(CHECK), 0x4B37);
+        assert_eq!(ChecksumAlgorithm::Crc16Modbus.to_bytes(0x4B37), vec![0x37, 0x4B]);
+        assert_eq!(ChecksumAlgorithm::Crc16Ccitt.compute(CHECK), 0x29B1);
+        assert_eq!(ChecksumAlgorithm::Crc16Ccitt.to_bytes(0x29B1), vec![0x29, 0xB1]);
+        assert_eq!(ChecksumAlgorithm::Crc32.compute(CHECK), 0xCBF4_3926);
+        assert_eq!(ChecksumAlgorithm::Crc32.to_bytes(0xCBF4_3926), vec![0xCB, 0xF4, 0x39, 0x26]);
+    }
+
+    #[test]
+    fn insert_before_trailer() {
+        let checksum = Checksum::parse_spec("crc16-modbus").unwrap();
+        let mut buffer = b"123456789\r\n".to_vec();
+        assert_eq!(checksum.insert(&mut buffer, 2).unwrap(), vec![0x37, 0x4B]);
+        assert_eq!(buffer, b"123456789\x37\x4B\r\n".to_vec());
+        assert_eq!(checksum.verify(&buffer, b"\r\n"), ChecksumResult::Ok);
+    }
+
+    #[test]
+    fn verify_mismatch_and_too_short() {
+        let checksum = Checksum::parse_spec("crc16-modbus:1:0").unwrap();
+        assert_eq!(checksum.verify(b"\x01123456789\x37\x4B", b""), ChecksumResult::Ok);
+        assert_eq!(checksum.verify(b"\x01123456789\x4B\x37", b""), ChecksumResult::Mismatch(vec![0x4B, 0x37], vec![0x37, 0x4B]));
+        assert_eq!(checksum.verify(b"\x37", b""), ChecksumResult::TooShort);
+        assert_eq!(checksum.verify(b"\x37\x4B", b""), ChecksumResult::TooShort);
+    }
+}
@@ -0,0 +1,169 @@
+use std::fmt;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::{self, LineEnding};
+use crate::config;
+use crate::rokit_error::RokitError;
+use crate::text_encoding::TextEncoding;
+
+const COMMAND_LIBRARY_FILE : &str = "commands.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum PayloadKind {
+    #[default]
+    Text,
+    Hex,
+}
+
+impl PayloadKind {
+    pub const ALL: [PayloadKind; 2] = [PayloadKind::Text, PayloadKind::Hex];
+}
+
+impl fmt::Display for PayloadKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            PayloadKind::Text => "文本",
+            PayloadKind::Hex => "HEX",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct QuickCommand {
+    pub name:String,
+    #[serde(default)]
+    pub group:String,
+    #[serde(default)]
+    pub kind:PayloadKind,
+    pub payload:String,
+    #[serde(default)]
+    pub encoding:TextEncoding,
+    #[serde(default)]
+    pub line_ending:LineEnding,
+}
+
+impl QuickCommand {
+    pub fn to_bytes(&self) -> Result<(Vec<u8>, String), RokitError> {
+        match self.kind {
+            PayloadKind::Text => {
+                let buffer = common::text_to_bytes(&self.payload, self.encoding, true, self.line_ending)?;
+                let display = format!("[{}] {} [{}]", self.name, self.payload, common::bytes_to_hex(&buffer));
+                Ok((buffer, display))
+            },
+            PayloadKind::Hex => {
+                let buffer = common::parse_hex(self.payload.clone())?;
+                let display = format!("[{}] {}", self.name, common::bytes_to_hex(&buffer));
+                Ok((buffer, display))
+            }
+        }
+    }
+
+    // Length of the line ending at the end of `to_bytes`.
+    pub fn trailer_len(&self) -> usize {
+        match self.kind {
+            PayloadKind::Text => self.line_ending.to_bytes(self.encoding).len(),
+            PayloadKind::Hex => 0
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CommandLibrary {
+    pub commands:Vec<QuickCommand>,
+}
+
+impl CommandLibrary {
+    pub fn load() -> Result<CommandLibrary, RokitError> {
+        let path = config::config_path(COMMAND_LIBRARY_FILE)?;
+        if !path.exists() {
+            return Ok(CommandLibrary::default());
+        }
+        config::load_json(&path)
+    }
+
+    pub fn save(&self) -> Result<(), RokitError> {
+        config::save_json(&config::config_path(COMMAND_LIBRARY_FILE)?, self)
+    }
+
+    // A command with the same group and name replaces the existing one in place.
+    // Returns the number of added and replaced commands.
+    pub fn import(&mut self, path:&Path) -> Result<(usize, usize), RokitError> {
+        let imported : CommandLibrary = config::load_json(path)?;
+        let (mut added, mut replaced) = (0, 0);
+        for command in imported.commands {
+            match self.commands.iter_mut().find(|c| c.group == command.group && c.name == command.name) {
+                Some(existing) => {
+                    *existing = command;
+                    replaced += 1;
+                },
+                None => {
+                    self.commands.push(command);
+                    added += 1;
+                }
+            }
+        }
+        Ok((added, replaced))
+    }
+
+    pub fn export(&self, path:&Path) -> Result<(), RokitError> {
+        config::save_json(path, self)
+    }
+
+    // Commands of a group are listed together, groups in order of first appearance.
+    pub fn display_order(&self) -> Vec<usize> {
+        let mut groups : Vec<&str> = Vec::new();
+        for command in self.commands.iter() {
+            if !groups.contains(&command.group.as_str()) {
+                groups.push(command.group.as_str());
+            }
+        }
+        groups.iter()
+            .flat_map(|g| self.commands.iter().enumerate().filter(move |(_, c)| c.group == *g).map(|(i, _)| i))
+            .collect()
+    }
+
+    pub fn move_up(&mut self, index:usize) {
+        let group = match self.commands.get(index) {
+            Some(x) => x.group.clone(),
+            None => return
+        };
+        if let Some(other) = (0..index).rev().find(|i| self.commands[*i].group == group) {
+            self.commands.swap(index, other);
+        }
+    }
+
+    pub fn move_down(&mut self, index:usize) {
+        let group = match self.commands.get(index) {
+            Some(x) => x.group.clone(),
+            None => return
+        };
+        if let Some(other) = (index + 1..self.commands.len()).find(|i| self.commands[*i].group == group) {
+            self.commands.swap(index, other);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(group:&str, name:&str, payload:&str) -> QuickCommand {
+        QuickCommand{ name:name.to_string(), group:group.to_string(), payload:payload.to_string(), ..QuickCommand::default() }
+    }
+
+    #[test]
+    fn import_replaces_same_group_and_name() {
+        let path = std::env::temp_dir().join(format!("rokit-import-{}.json", std::process::id()));
+        let file = CommandLibrary{ commands:vec![command("A", "ping", "new"), command("B", "ping", "b"), command("A", "stop", "s")] };
+        file.export(&path).unwrap();
+        let mut library = CommandLibrary{ commands:vec![command("A", "ping", "old"), command("", "other", "o")] };
+        let result = library.import(&path);
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(result.unwrap(), (2, 1));
+        let payloads : Vec<&str> = library.commands.iter().map(|c| c.payload.as_str()).collect();
+        assert_eq!(payloads, vec!["new", "o", "b", "s"]);
+    }
+}
@@ -0,0 +1,356 @@
+
+use std::net::{IpAddr, Ipv6Addr, SocketAddr, SocketAddrV6, ToSocketAddrs};
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::rokit_error::RokitError;
+use crate::text_encoding::TextEncoding;
+
+pub const DEFAULT_BUFFER_SIZE : usize = 1024;
+pub const MAX_BUFFER_SIZE : usize = 1024 * 1024;
+
+pub fn parse_ip_port(ip:String, port:String) -> Result<SocketAddr, RokitError> {
+    let addrs = resolve_ip_port(ip.clone(), port)?;
+    match addrs.into_iter().next() {
+        Some(x) => Ok(x),
+        None => Err(RokitError::addr_parse("无法解析主机:".to_string() + ip.as_str()))
+    }
+}
+
+pub fn resolve_ip_port(ip:String, port:String) -> Result<Vec<SocketAddr>, RokitError> {
+    let parse_port = match port.trim().parse::<u16>() {
+        Ok(x) => x,
+        _ => return Err(RokitError::addr_parse("端口格式错误:".to_string() + port.as_str()))
+    };
+    let host = ip.trim();
+    if host.is_empty() {
+        return Err(RokitError::addr_parse("IP地址格式错误:".to_string() + ip.as_str()))
+    }
+    let host = match (host.strip_prefix('['), host.ends_with(']')) {
+        (Some(h), true) => &h[..h.len() - 1],
+        (None, false) => host,
+        _ => return Err(RokitError::addr_parse("IP地址格式错误, 方括号不完整:".to_string() + ip.as_str()))
+    };
+
+    if let Some((addr, scope)) = host.split_once('%') {
+        let v6 = match addr.parse::<Ipv6Addr>() {
+            Ok(x) => x,
+            Err(_) => return Err(RokitError::addr_parse("IP地址格式错误:".to_string() + ip.as_str()))
+        };
+        let scope_id = parse_scope_id(scope)?;
+        return Ok(vec![SocketAddr::V6(SocketAddrV6::new(v6, parse_port, 0, scope_id))]);
+    }
+    if let Ok(x) = host.parse::<IpAddr>() {
+        return Ok(vec![SocketAddr::new(x, parse_port)]);
+    }
+
+    match (host, parse_port).to_socket_addrs() {
+        Ok(iter) => {
+            let mut addrs : Vec<SocketAddr> = Vec::new();
+            for a in iter {
+                if !addrs.contains(&a) {
+                    addrs.push(a);
+                }
+            }
+            if addrs.is_empty() {
+                return Err(RokitError::addr_parse("无法解析主机:".to_string() + ip.as_str()))
+            }
+            Ok(addrs)
+        },
+        Err(e) => Err(RokitError::addr_parse(format!("无法解析主机:{} {}", ip, e)))
+    }
+}
+
+fn parse_scope_id(scope:&str) -> Result<u32, RokitError> {
+    if let Ok(x) = scope.parse::<u32>() {
+        return Ok(x);
+    }
+    interface_index(scope).ok_or_else(|| RokitError::addr_parse("网络接口不存在:".to_string() + scope))
+}
+
+#[cfg(unix)]
+fn interface_index(name:&str) -> Option<u32> {
+    let c_name = std::ffi::CString::new(name).ok()?;
+    let index = unsafe { libc::if_nametoindex(c_name.as_ptr()) };
+    if index == 0 { None } else { Some(index) }
+}
+
+#[cfg(not(unix))]
+fn interface_index(_name:&str) -> Option<u32> {
+    None
+}
+
+pub fn parse_buffer_size(input:String) -> Result<usize, RokitError> {
+    match input.trim().parse::<usize>() {
+        Ok(x) if x > 0 && x <= MAX_BUFFER_SIZE => Ok(x),
+        _ => Err(RokitError::decode(format!("接收缓冲格式错误, 范围1-{}:{}", MAX_BUFFER_SIZE, input)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum LineEnding {
+    #[default]
+    None,
+    Lf,
+    Cr,
+    CrLf,
+}
+
+impl LineEnding {
+    pub const ALL: [LineEnding; 4] = [LineEnding::None, LineEnding::Lf, LineEnding::Cr, LineEnding::CrLf];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::None => "",
+            LineEnding::Lf => "\n",
+            LineEnding::Cr => "\r",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+
+    pub fn to_bytes(&self, encoding:TextEncoding) -> Vec<u8> {
+        encoding.encode(self.as_str())
+    }
+
+    pub fn from_name(name:&str) -> Option<LineEnding> {
+        match name.to_ascii_lowercase().as_str() {
+            "none" => Some(LineEnding::None),
+            "lf" => Some(LineEnding::Lf),
+            "cr" => Some(LineEnding::Cr),
+            "crlf" => Some(LineEnding::CrLf),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for LineEnding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            LineEnding::None => "无换行",
+            LineEnding::Lf => "LF",
+            LineEnding::Cr => "CR",
+            LineEnding::CrLf => "CRLF",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+pub fn text_to_bytes(text:&str, encoding:TextEncoding, escape:bool, line_ending:LineEnding) -> Result<Vec<u8>, RokitError> {
+    let mut buffer = if escape {
+        unescape(text, encoding)?
+    } else {
+        encoding.encode(text)
+    };
+    buffer.extend(line_ending.to_bytes(encoding));
+    Ok(buffer)
+}
+
+pub fn find_bytes(haystack:&[u8], needle:&[u8]) -> Option<usize> {
+    if needle.is_empty() {
+        return Some(0);
+    }
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+pub fn format_received(buffer:&[u8], hex_display:bool, encoding:TextEncoding) -> String {
+    if hex_display {
+        format!("{}字节\n{}", buffer.len(), hex_dump(buffer))
+    } else {
+        encoding.decode(buffer)
+    }
+}
+
+pub fn unescape(input:&str, encoding:TextEncoding) -> Result<Vec<u8>, RokitError> {
+    let mut buffer : Vec<u8> = Vec::new();
+    let mut text = String::new();
+    let mut chars = input.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        let escaped = match chars.next() {
+            Some(x) => x,
+            None => return Err(RokitError::decode("转义格式错误, 结尾多余的'\\'".to_string()))
+        };
+        let c = match escaped {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            '0' => '\0',
+            'a' => '\x07',
+            'b' => '\x08',
+            'f' => '\x0C',
+            'v' => '\x0B',
+            'e' => '\x1B',
+            '\\' | '\'' | '"' => escaped,
+            'x' => {
+                let digits : String = chars.by_ref().take(2).collect();
+                let byte = match u8::from_str_radix(&digits, 16) {
+                    Ok(x) if digits.len() == 2 && digits.chars().all(|c| c.is_ascii_hexdigit()) => x,
+                    _ => return Err(RokitError::decode("转义格式错误, 格式为'\\xHH':\\x".to_string() + digits.as_str()))
+                };
+                buffer.extend(encoding.encode(&text));
+                text.clear();
+                buffer.push(byte);
+                continue;
+            },
+            _ => return Err(RokitError::decode(format!("转义格式错误, 不支持'\\{}'", escaped)))
+        };
+        text.push(c);
+    }
+    buffer.extend(encoding.encode(&text));
+    Ok(buffer)
+}
+
+pub fn ascii_to_bytes(input:String) -> Result<Vec<u8>, RokitError> {
+    let split_input : Vec<&str> = input.as_str().split(",").collect();
+    let mut buffer : Vec<u8> = Vec::new();
+    for s in split_input {
+        let temp = s.parse::<u8>();
+        match temp {
+            Ok(x) => buffer.push(x),
+            Err(_) => return Err(RokitError::decode("用户输入错误, 格式为'65,66,67' => 'ABC'".to_string()))
+        }
+    }
+    Ok(buffer)
+}
+
+pub fn parse_hex(input:String) -> Result<Vec<u8>, RokitError> {
+    let mut buffer : Vec<u8> = Vec::new();
+    for token in input.split(|c:char| c.is_whitespace() || c == ',' || c == ';').filter(|t| !t.is_empty()) {
+        let digits = token.strip_prefix("0x").or_else(|| token.strip_prefix("0X")).unwrap_or(token);
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(RokitError::decode("HEX格式错误, 格式为'0A 1B ff'或'0x0a,0x1b':".to_string() + token));
+        }
+        if digits.len() == 1 {
+            buffer.push(u8::from_str_radix(digits, 16).unwrap());
+            continue;
+        }
+        if digits.len() % 2 != 0 {
+            return Err(RokitError::decode("HEX格式错误, 字节数不完整:".to_string() + token));
+        }
+        for i in (0..digits.len()).step_by(2) {
+            buffer.push(u8::from_str_radix(&digits[i..i + 2], 16).unwrap());
+        }
+    }
+    if buffer.is_empty() {
+        return Err(RokitError::decode("HEX格式错误, 格式为'0A 1B ff'或'0x0a,0x1b'".to_string()));
+    }
+    Ok(buffer)
+}
+
+pub fn bytes_to_hex(buffer:&[u8]) -> String {
+    buffer.iter().map(|b| format!("{:02X}", b)).collect::<Vec<String>>().join(" ")
+}
+
+pub fn hex_dump(buffer:&[u8]) -> String {
+    let mut lines : Vec<String> = Vec::new();
+    for (i, chunk) in buffer.chunks(16).enumerate() {
+        let mut hex = String::new();
+        for j in 0..16 {
+            if j == 8 {
+                hex.push(' ');
+            }
+            match chunk.get(j) {
+                Some(b) => hex += format!("{:02x} ", b).as_str(),
+                None => hex += "   "
+            }
+        }
+        let ascii : String = chunk.iter().map(|b| if b.is_ascii_graphic() || *b == b' ' { *b as char } else { '.' }).collect();
+        lines.push(format!("{:08x}  {} |{}|", i * 16, hex, ascii));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unescape_sequences() {
+        let utf8 = TextEncoding::Utf8;
+        assert_eq!(unescape("a\\r\\n\\t\\0\\\\\\'\\\"", utf8).unwrap(), b"a\r\n\t\0\\'\"".to_vec());
+        assert_eq!(unescape("\\a\\b\\f\\v\\e", utf8).unwrap(), vec![0x07, 0x08, 0x0C, 0x0B, 0x1B]);
+        assert_eq!(unescape("\\x00\\xff\\xA5z", utf8).unwrap(), vec![0x00, 0xFF, 0xA5, b'z']);
+        assert_eq!(unescape("中\\x0D", utf8).unwrap(), vec![0xE4, 0xB8, 0xAD, 0x0D]);
+        // Raw bytes are not run through the encoding.
+        assert_eq!(unescape("A\\xFFB", TextEncoding::Utf16Le).unwrap(), vec![b'A', 0x00, 0xFF, b'B', 0x00]);
+        assert_eq!(unescape("", utf8).unwrap(), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn unescape_errors() {
+        let utf8 = TextEncoding::Utf8;
+        for input in ["\\x+1", "\\x-1", "\\x1", "\\x", "\\xG0", "\\x 1", "\\x中1", "abc\\", "\\q"] {
+            assert!(unescape(input, utf8).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn parse_hex_separators_and_prefixes() {
+        assert_eq!(parse_hex("0A 1B ff".to_string()).unwrap(), vec![0x0A, 0x1B, 0xFF]);
+        assert_eq!(parse_hex("0x0a,0x1b;0XFF\t\n7f".to_string()).unwrap(), vec![0x0A, 0x1B, 0xFF, 0x7F]);
+        assert_eq!(parse_hex("0a1b2c 0x0d0e".to_string()).unwrap(), vec![0x0A, 0x1B, 0x2C, 0x0D, 0x0E]);
+        assert_eq!(parse_hex(" a, 0x1 ".to_string()).unwrap(), vec![0x0A, 0x01]);
+    }
+
+    #[test]
+    fn parse_hex_errors() {
+        for input in ["abc", "0x123", "0G", "zz", "0x", "1-2", "", " ,; "] {
+            assert!(parse_hex(input.to_string()).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn hex_dump_layout() {
+        let mut buffer = b"0123456789ABCDEF".to_vec();
+        buffer.extend_from_slice(&[b'G', 0x00, b'\n', b' ']);
+        let dump = hex_dump(&buffer);
+        let lines : Vec<&str> = dump.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], "00000000  30 31 32 33 34 35 36 37  38 39 41 42 43 44 45 46  |0123456789ABCDEF|");
+        assert_eq!(lines[1], format!("00000010  47 00 0a 20 {}|G.. |", " ".repeat(38)));
+        assert_eq!(hex_dump(&[]), "");
+        assert_eq!(bytes_to_hex(&[0x00, 0xAB, 0x0F]), "00 AB 0F");
+    }
+
+    fn resolve(ip:&str, port:&str) -> Result<Vec<SocketAddr>, RokitError> {
+        resolve_ip_port(ip.to_string(), port.to_string())
+    }
+
+    #[test]
+    fn resolve_literals() {
+        assert_eq!(resolve(" 127.0.0.1 ", "8080").unwrap(), vec!["127.0.0.1:8080".parse().unwrap()]);
+        assert_eq!(resolve("::1", "80").unwrap(), vec!["[::1]:80".parse().unwrap()]);
+        assert_eq!(resolve("[2001:db8::1]", " 443 ").unwrap(), vec!["[2001:db8::1]:443".parse().unwrap()]);
+        assert_eq!(resolve("[fe80::1%3]", "80").unwrap(), vec![SocketAddr::V6(SocketAddrV6::new("fe80::1".parse().unwrap(), 80, 0, 3))]);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn resolve_scope_by_interface_name() {
+        let index = interface_index("lo").unwrap();
+        assert!(index > 0);
+        assert_eq!(resolve("fe80::1%lo", "80").unwrap(), vec![SocketAddr::V6(SocketAddrV6::new("fe80::1".parse().unwrap(), 80, 0, index))]);
+        assert!(resolve("fe80::1%rokit-none0", "80").is_err());
+    }
+
+    #[test]
+    fn resolve_hostname() {
+        let addrs = resolve("localhost", "7").unwrap();
+        assert!(!addrs.is_empty());
+        assert!(addrs.iter().all(|a| a.ip().is_loopback() && a.port() == 7));
+    }
+
+    #[test]
+    fn resolve_errors() {
+        let cases = [("127.0.0.1", ""), ("127.0.0.1", "http"), ("127.0.0.1", "65536"), ("", "80"), ("  ", "80"),
+            ("[::1", "80"), ("::1]", "80"), ("[]", "80"), ("127.0.0.1%1", "80"), ("[::1]:80", "80")];
+        for (ip, port) in cases {
+            assert!(resolve(ip, port).is_err(), "{} {}", ip, port);
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::rokit_error::RokitError;
+
+const CONFIG_DIR_NAME : &str = "rokit";
+
+pub fn config_dir() -> Result<PathBuf, RokitError> {
+    match dirs::config_dir() {
+        Some(dir) => Ok(dir.join(CONFIG_DIR_NAME)),
+        None => Err(RokitError::decode("找不到配置目录".to_string()))
+    }
+}
+
+pub fn config_path(name:&str) -> Result<PathBuf, RokitError> {
+    Ok(config_dir()?.join(name))
+}
+
+pub fn load_json<T: DeserializeOwned>(path:&Path) -> Result<T, RokitError> {
+    let text = match fs::read_to_string(path) {
+        Ok(x) => x,
+        Err(e) => return Err(RokitError::io(format!("读取错误:{}", path.display()).as_str(), e))
+    };
+    match serde_json::from_str(&text) {
+        Ok(x) => Ok(x),
+        Err(e) => Err(RokitError::decode(format!("格式错误:{} {}", path.display(), e)))
+    }
+}
+
+pub fn save_json<T: Serialize>(path:&Path, value:&T) -> Result<(), RokitError> {
+    if let Some(parent) = path.parent() {
+        if let Err(e) = fs::create_dir_all(parent) {
+            return Err(RokitError::io(format!("写入错误:{}", parent.display()).as_str(), e));
+        }
+    }
+    let text = match serde_json::to_string_pretty(value) {
+        Ok(x) => x,
+        Err(e) => return Err(RokitError::decode(format!("格式错误:{} {}", path.display(), e)))
+    };
+    match fs::write(path, text) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(RokitError::io(format!("写入错误:{}", path.display()).as_str(), e))
+    }
+}
@@ -0,0 +1,313 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::common;
+use crate::rokit_error::RokitError;
+use crate::text_encoding::TextEncoding;
+
+pub const FRAME_SIZE_MAX : usize = 16 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum FramingMode {
+    #[default]
+    Raw,
+    Delimiter,
+    FixedLength,
+    LengthPrefixed,
+}
+
+impl FramingMode {
+    pub const ALL: [FramingMode; 4] = [FramingMode::Raw, FramingMode::Delimiter, FramingMode::FixedLength, FramingMode::LengthPrefixed];
+}
+
+impl fmt::Display for FramingMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            FramingMode::Raw => "不分帧",
+            FramingMode::Delimiter => "分隔符",
+            FramingMode::FixedLength => "固定长度",
+            FramingMode::LengthPrefixed => "长度前缀",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// Length-prefixed frames are `length_offset` header bytes, a `length_size` byte
+// length field, then `value + length_adjustment` bytes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Framing {
+    pub mode:FramingMode,
+    pub delimiter:String,
+    pub fixed_length:usize,
+    pub length_size:u8,
+    pub big_endian:bool,
+    pub length_offset:usize,
+    pub length_adjustment:i64,
+}
+
+impl Default for Framing {
+    fn default() -> Self {
+        Framing{
+            mode:FramingMode::Raw,
+            delimiter:String::from("\\n"),
+            fixed_length:8,
+            length_size:2,
+            big_endian:true,
+            length_offset:0,
+            length_adjustment:0,
+        }
+    }
+}
+
+impl Framing {
+    pub const LENGTH_SIZES: [u8; 3] = [1, 2, 4];
+
+    // raw | delim:<转义文本> | fixed:<长度> | len:<1|2|4>[:be|le[:偏移[:调整]]]
+    pub fn parse_spec(spec:&str) -> Result<Framing, RokitError> {
+        let mut framing = Framing::default();
+        let (kind, args) = match spec.split_once(':') {
+            Some((kind, args)) => (kind, args),
+            None => (spec, "")
+        };
+        match kind {
+            "raw" => {},
+            "delim" => {
+                framing.mode = FramingMode::Delimiter;
+                framing.delimiter = args.to_string();
+            },
+            "fixed" => {
+                framing.mode = FramingMode::FixedLength;
+                framing.fixed_length = parse_number("固定长度", args)?;
+            },
+            "len" => {
+                framing.mode = FramingMode::LengthPrefixed;
+                let mut parts = args.split(':');
+                framing.length_size = parse_number("长度字节", parts.next().unwrap_or(""))?;
+                framing.big_endian = match parts.next() {
+                    None | Some("be") => true,
+                    Some("le") => false,
+                    Some(x) => return Err(RokitError::decode("字节序格式错误:".to_string() + x))
+                };
+                if let Some(x) = parts.next() {
+                    framing.length_offset = parse_number("长度偏移", x)?;
+                }
+                if let Some(x) = parts.next() {
+                    framing.length_adjustment = parse_number("长度调整", x)?;
+                }
+            },
+            _ => return Err(RokitError::decode("分帧格式错误:".to_string() + spec))
+        }
+        framing.validate()?;
+        Ok(framing)
+    }
+
+    // The part of the spec after `<kind>:`, as edited in the session view.
+    pub fn args(&self) -> String {
+        match self.mode {
+            FramingMode::Raw => String::new(),
+            FramingMode::Delimiter => self.delimiter.clone(),
+            FramingMode::FixedLength => self.fixed_length.to_string(),
+            FramingMode::LengthPrefixed => format!("{}:{}:{}:{}", self.length_size, if self.big_endian { "be" } else { "le" },
+                self.length_offset, self.length_adjustment),
+        }
+    }
+
+    pub fn from_args(mode:FramingMode, args:&str) -> Result<Framing, RokitError> {
+        let kind = match mode {
+            FramingMode::Raw => return Ok(Framing::default()),
+            FramingMode::Delimiter => "delim",
+            FramingMode::FixedLength => "fixed",
+            FramingMode::LengthPrefixed => "len",
+        };
+        Framing::parse_spec(format!("{}:{}", kind, args).as_str())
+    }
+
+    pub fn validate(&self) -> Result<(), RokitError> {
+        match self.mode {
+            FramingMode::Raw => Ok(()),
+            FramingMode::Delimiter => {
+                self.delimiter_bytes()?;
+                Ok(())
+            },
+            FramingMode::FixedLength if self.fixed_length == 0 || self.fixed_length > FRAME_SIZE_MAX => {
+                Err(RokitError::decode(format!("固定长度错误:{}", self.fixed_length)))
+            },
+            FramingMode::FixedLength => Ok(()),
+            FramingMode::LengthPrefixed if !Framing::LENGTH_SIZES.contains(&self.length_size) => {
+                Err(RokitError::decode(format!("长度字节错误:{}, 只支持1, 2, 4", self.length_size)))
+            },
+            FramingMode::LengthPrefixed => Ok(())
+        }
+    }
+
+    // The bytes closing every frame, a checksum sits in front of them.
+    pub fn trailer(&self) -> Vec<u8> {
+        match self.mode {
+            FramingMode::Delimiter => self.delimiter_bytes().unwrap_or_default(),
+            _ => Vec::new()
+        }
+    }
+
+    fn delimiter_bytes(&self) -> Result<Vec<u8>, RokitError> {
+        let delimiter = common::unescape(&self.delimiter, TextEncoding::default())?;
+        if delimiter.is_empty() {
+            return Err(RokitError::decode("分隔符为空".to_string()));
+        }
+        Ok(delimiter)
+    }
+
+    fn frame_length(&self, buffer:&[u8]) -> Result<Option<usize>, RokitError> {
+        let header = self.length_offset + self.length_size as usize;
+        if buffer.len() < header {
+            return Ok(None);
+        }
+        let field = &buffer[self.length_offset..header];
+        let mut value : u64 = 0;
+        for i in 0..field.len() {
+            let byte = if self.big_endian { field[i] } else { field[field.len() - 1 - i] };
+            value = (value << 8) | byte as u64;
+        }
+        let length = header as i64 + value as i64 + self.length_adjustment;
+        if length < header as i64 || length as usize > FRAME_SIZE_MAX {
+            return Err(RokitError::decode(format!("帧长度错误:{}", length)));
+        }
+        Ok(Some(length as usize))
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(name:&str, input:&str) -> Result<T, RokitError> {
+    match input.trim().parse::<T>() {
+        Ok(x) => Ok(x),
+        Err(_) => Err(RokitError::decode(format!("{}格式错误:{}", name, input)))
+    }
+}
+
+// Reassembles one byte stream (one peer) into frames.
+#[derive(Debug, Clone)]
+pub struct Framer {
+    framing:Framing,
+    delimiter:Vec<u8>,
+    buffer:Vec<u8>,
+}
+
+impl Framer {
+    pub fn new(framing:Framing) -> Self {
+        let delimiter = framing.delimiter_bytes().unwrap_or_default();
+        Framer{
+            framing,
+            delimiter,
+            buffer:Vec::new(),
+        }
+    }
+
+    // Returns the complete frames together with any error. On a length error the
+    // remaining buffered bytes are dropped so the stream can resync.
+    pub fn push(&mut self, data:&[u8]) -> (Vec<Vec<u8>>, Option<RokitError>) {
+        if self.framing.mode == FramingMode::Raw || (self.framing.mode == FramingMode::Delimiter && self.delimiter.is_empty()) {
+            return (vec![data.to_vec()], None);
+        }
+        self.buffer.extend_from_slice(data);
+        let mut frames = Vec::new();
+        loop {
+            let length = match self.framing.mode {
+                FramingMode::Delimiter => match common::find_bytes(&self.buffer, &self.delimiter) {
+                    Some(index) => Some(index + self.delimiter.len()),
+                    None => None
+                },
+                FramingMode::FixedLength => Some(self.framing.fixed_length),
+                FramingMode::LengthPrefixed => match self.framing.frame_length(&self.buffer) {
+                    Ok(x) => x,
+                    Err(e) => {
+                        self.buffer.clear();
+                        return (frames, Some(e));
+                    }
+                },
+                FramingMode::Raw => None
+            };
+            match length {
+                Some(length) if length <= self.buffer.len() => {
+                    frames.push(self.buffer.drain(..length).collect());
+                },
+                _ => break
+            }
+        }
+        if self.buffer.len() > FRAME_SIZE_MAX {
+            self.buffer.clear();
+            return (frames, Some(RokitError::decode(format!("帧长度超过{}字节", FRAME_SIZE_MAX))));
+        }
+        (frames, None)
+    }
+
+    // Incomplete data left when the stream ends.
+    pub fn flush(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn push_all(framer:&mut Framer, reads:&[&[u8]]) -> Vec<Vec<u8>> {
+        let mut frames = Vec::new();
+        for data in reads {
+            let (mut pushed, error) = framer.push(data);
+            assert!(error.is_none());
+            frames.append(&mut pushed);
+        }
+        frames
+    }
+
+    #[test]
+    fn delimiter_split_across_reads() {
+        let mut framer = Framer::new(Framing::parse_spec("delim:\\r\\n").unwrap());
+        let frames = push_all(&mut framer, &[b"ab\r", b"\ncd\r\nef", b"\r", b"\n"]);
+        assert_eq!(frames, vec![b"ab\r\n".to_vec(), b"cd\r\n".to_vec(), b"ef\r\n".to_vec()]);
+        assert!(framer.flush().is_empty());
+    }
+
+    #[test]
+    fn fixed_length_keeps_remainder() {
+        let mut framer = Framer::new(Framing::parse_spec("fixed:3").unwrap());
+        let frames = push_all(&mut framer, &[b"abcd", b"ef", b"gh"]);
+        assert_eq!(frames, vec![b"abc".to_vec(), b"def".to_vec()]);
+        assert_eq!(framer.flush(), b"gh".to_vec());
+    }
+
+    #[test]
+    fn length_prefix_partial_header() {
+        let mut framer = Framer::new(Framing::parse_spec("len:2:be").unwrap());
+        let frames = push_all(&mut framer, &[&[0x00], &[0x03, b'a'], &[b'b', b'c', 0x00, 0x01, b'd']]);
+        assert_eq!(frames, vec![vec![0x00, 0x03, b'a', b'b', b'c'], vec![0x00, 0x01, b'd']]);
+    }
+
+    #[test]
+    fn length_prefix_offset_and_adjustment() {
+        // Modbus TCP: 4 header bytes, then the length of the rest.
+        let mut framer = Framer::new(Framing::parse_spec("len:2:be:4:0").unwrap());
+        let frame = [0x00, 0x01, 0x00, 0x00, 0x00, 0x06, 0x01, 0x03, 0x00, 0x00, 0x00, 0x0A];
+        assert_eq!(push_all(&mut framer, &[&frame[..5], &frame[5..]]), vec![frame.to_vec()]);
+        // Little endian length after a 1 byte header, counting the whole frame.
+        let mut framer = Framer::new(Framing::parse_spec("len:2:le:1:-3").unwrap());
+        let frames = push_all(&mut framer, &[&[0xAA, 0x05, 0x00, b'x', b'y', 0xAA, 0x06, 0x00]]);
+        assert_eq!(frames, vec![vec![0xAA, 0x05, 0x00, b'x', b'y']]);
+        assert_eq!(framer.flush(), vec![0xAA, 0x06, 0x00]);
+    }
+
+    #[test]
+    fn length_error_keeps_earlier_frames() {
+        let mut framer = Framer::new(Framing::parse_spec("len:1:be:0:-5").unwrap());
+        let (frames, error) = framer.push(&[0x06, b'a', 0x06, b'b', 0x00, 0x01]);
+        assert_eq!(frames, vec![vec![0x06, b'a'], vec![0x06, b'b']]);
+        assert!(error.is_some());
+        assert!(framer.flush().is_empty());
+    }
+
+    #[test]
+    fn raw_passes_reads_through() {
+        let mut framer = Framer::new(Framing::default());
+        assert_eq!(push_all(&mut framer, &[b"abc"]), vec![b"abc".to_vec()]);
+    }
+}
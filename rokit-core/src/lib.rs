@@ -0,0 +1,19 @@
+pub mod rokit_error;
+pub mod common;
+pub mod config;
+pub mod command;
+pub mod log;
+pub mod text_encoding;
+pub mod tcp_client;
+pub mod tcp_server;
+pub mod udp_client;
+pub mod udp_server;
+pub mod transport;
+pub mod script;
+pub mod profile;
+pub mod pcap;
+pub mod replay;
+pub mod framing;
+pub mod checksum;
+pub mod modbus;
+pub mod service;
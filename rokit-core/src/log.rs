@@ -0,0 +1,289 @@
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::common;
+use crate::rokit_error::RokitError;
+
+pub fn generate_log(msg:String) -> String {
+    format_log(&Local::now(), msg.as_str())
+}
+
+fn format_log(time:&DateTime<Local>, msg:&str) -> String {
+
+    let fmt = "%H:%M:%S";
+    let date_str = time.format(fmt).to_string();
+    date_str + " " + msg + "\n"
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl LogFormat {
+    pub const ALL: [LogFormat; 2] = [LogFormat::Text, LogFormat::Json];
+}
+
+impl fmt::Display for LogFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            LogFormat::Text => "文本",
+            LogFormat::Json => "JSON行",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogDirection {
+    Send,
+    Receive,
+    Info,
+}
+
+impl LogDirection {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogDirection::Send => "send",
+            LogDirection::Receive => "receive",
+            LogDirection::Info => "info",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LogRecord {
+    pub time:DateTime<Local>,
+    pub direction:LogDirection,
+    pub peer:Option<SocketAddr>,
+    pub bytes:Option<Vec<u8>>,
+    pub message:String,
+}
+
+impl LogRecord {
+    pub fn new(direction:LogDirection, peer:Option<SocketAddr>, bytes:Option<&[u8]>, message:String) -> Self {
+        LogRecord{
+            time:Local::now(),
+            direction,
+            peer,
+            bytes:bytes.map(|b| b.to_vec()),
+            message,
+        }
+    }
+
+    pub fn info(message:String) -> Self {
+        LogRecord::new(LogDirection::Info, None, None, message)
+    }
+
+    pub fn to_line(&self, format:LogFormat) -> String {
+        match format {
+            LogFormat::Text => format_log(&self.time, self.message.as_str()),
+            LogFormat::Json => {
+                let value = serde_json::json!({
+                    "time": self.time.to_rfc3339_opts(chrono::SecondsFormat::Millis, false),
+                    "direction": self.direction.as_str(),
+                    "peer": self.peer.map(|a| a.to_string()),
+                    "bytes": self.bytes.as_ref().map(|b| common::bytes_to_hex(b)),
+                    "message": self.message,
+                });
+                value.to_string() + "\n"
+            }
+        }
+    }
+}
+
+// Appends records to `<stem>[-YYYYMMDD][-N].<ext>` next to `path`, starting a
+// new file each day (if `daily`) and whenever `max_size` would be exceeded.
+#[derive(Debug)]
+pub struct LogFile {
+    path:PathBuf,
+    format:LogFormat,
+    max_size:Option<u64>,
+    daily:bool,
+    date:String,
+    index:u32,
+    file:Option<File>,
+    size:u64,
+}
+
+impl LogFile {
+    pub fn open(path:&Path, format:LogFormat, max_size:Option<u64>, daily:bool) -> Result<Self, RokitError> {
+        let mut log_file = LogFile{
+            path:path.to_path_buf(),
+            format,
+            max_size,
+            daily,
+            date:Local::now().format("%Y%m%d").to_string(),
+            index:0,
+            file:None,
+            size:0,
+        };
+        log_file.open_file()?;
+        Ok(log_file)
+    }
+
+    pub fn current_path(&self) -> PathBuf {
+        let stem = match self.path.file_stem() {
+            Some(x) => x.to_string_lossy().to_string(),
+            None => String::from("rokit")
+        };
+        let mut name = stem;
+        if self.daily {
+            name = name + "-" + self.date.as_str();
+        }
+        if self.index > 0 {
+            name = format!("{}-{}", name, self.index);
+        }
+        if let Some(ext) = self.path.extension() {
+            name = name + "." + ext.to_string_lossy().as_ref();
+        }
+        self.path.with_file_name(name)
+    }
+
+    fn open_file(&mut self) -> Result<(), RokitError> {
+        if let Some(parent) = self.path.parent() {
+            if !parent.as_os_str().is_empty() {
+                if let Err(e) = fs::create_dir_all(parent) {
+                    return Err(RokitError::io("日志文件错误", e));
+                }
+            }
+        }
+        loop {
+            let path = self.current_path();
+            let file = match OpenOptions::new().create(true).append(true).open(&path) {
+                Ok(x) => x,
+                Err(e) => return Err(RokitError::io(format!("日志文件错误:{}", path.display()).as_str(), e))
+            };
+            let size = match file.metadata() {
+                Ok(x) => x.len(),
+                Err(_) => 0
+            };
+            match self.max_size {
+                Some(max_size) if size >= max_size => self.index += 1,
+                _ => {
+                    self.file = Some(file);
+                    self.size = size;
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    pub fn write(&mut self, record:&LogRecord) -> Result<(), RokitError> {
+        let line = record.to_line(self.format);
+        let date = record.time.format("%Y%m%d").to_string();
+        if self.daily && date != self.date {
+            self.date = date;
+            self.index = 0;
+            self.file = None;
+        }
+        if let Some(max_size) = self.max_size {
+            if self.size > 0 && self.size + line.len() as u64 > max_size {
+                self.index += 1;
+                self.file = None;
+            }
+        }
+        if self.file.is_none() {
+            self.open_file()?;
+        }
+        let res = match self.file {
+            Some(ref mut file) => file.write_all(line.as_bytes()),
+            None => Ok(())
+        };
+        match res {
+            Ok(_) => {
+                self.size += line.len() as u64;
+                Ok(())
+            },
+            Err(e) => Err(RokitError::io("日志写入错误", e))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name:&str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rokit-log-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn names(dir:&Path) -> Vec<String> {
+        let mut names : Vec<String> = fs::read_dir(dir).unwrap().map(|e| e.unwrap().file_name().to_string_lossy().to_string()).collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn size_rotation() {
+        let dir = temp_dir("size");
+        let mut log_file = LogFile::open(&dir.join("out.log"), LogFormat::Text, Some(32), false).unwrap();
+        for msg in ["first-line", "second-ln", "third-line"] {
+            log_file.write(&LogRecord::info(msg.to_string())).unwrap();
+        }
+        assert_eq!(log_file.current_path(), dir.join("out-2.log"));
+        assert_eq!(names(&dir), vec!["out-1.log", "out-2.log", "out.log"]);
+        for (name, msg) in [("out.log", "first-line"), ("out-1.log", "second-ln"), ("out-2.log", "third-line")] {
+            let content = fs::read_to_string(dir.join(name)).unwrap();
+            assert!(content.ends_with(&format!(" {}\n", msg)), "{}: {}", name, content);
+            assert_eq!(content.lines().count(), 1);
+        }
+        drop(log_file);
+
+        let log_file = LogFile::open(&dir.join("out.log"), LogFormat::Text, Some(19), false).unwrap();
+        assert_eq!(log_file.current_path(), dir.join("out-3.log"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn daily_rotation() {
+        let dir = temp_dir("daily");
+        let mut log_file = LogFile::open(&dir.join("out.log"), LogFormat::Text, Some(30), true).unwrap();
+        let today = Local::now();
+        let tomorrow = today + chrono::Duration::days(1);
+        for (time, msg) in [(today, "a"), (today, "b"), (today, "c"), (tomorrow, "d")] {
+            let mut record = LogRecord::info(msg.to_string());
+            record.time = time;
+            log_file.write(&record).unwrap();
+        }
+        let day1 = today.format("%Y%m%d").to_string();
+        let day2 = tomorrow.format("%Y%m%d").to_string();
+        assert_eq!(log_file.current_path(), dir.join(format!("out-{}.log", day2)));
+        assert_eq!(names(&dir), vec![format!("out-{}-1.log", day1), format!("out-{}.log", day1), format!("out-{}.log", day2)]);
+        assert_eq!(fs::read_to_string(dir.join(format!("out-{}.log", day1))).unwrap().lines().count(), 2);
+        assert!(fs::read_to_string(dir.join(format!("out-{}.log", day2))).unwrap().ends_with(" d\n"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn json_lines() {
+        let dir = temp_dir("json");
+        let path = dir.join("out.jsonl");
+        let mut log_file = LogFile::open(&path, LogFormat::Json, None, false).unwrap();
+        let peer : SocketAddr = "127.0.0.1:502".parse().unwrap();
+        log_file.write(&LogRecord::new(LogDirection::Send, Some(peer), Some(&[0x01, 0xAB]), "发送".to_string())).unwrap();
+        log_file.write(&LogRecord::info("信息".to_string())).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+        let lines : Vec<serde_json::Value> = content.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["direction"], "send");
+        assert_eq!(lines[0]["peer"], "127.0.0.1:502");
+        assert_eq!(lines[0]["bytes"], "01 AB");
+        assert_eq!(lines[0]["message"], "发送");
+        assert!(DateTime::parse_from_rfc3339(lines[0]["time"].as_str().unwrap()).is_ok());
+        assert_eq!(lines[1]["direction"], "info");
+        assert!(lines[1]["peer"].is_null());
+        assert!(lines[1]["bytes"].is_null());
+    }
+}
@@ -0,0 +1,748 @@
+use std::fmt;
+
+use crate::common;
+use crate::framing::{Framing, FramingMode};
+use crate::rokit_error::RokitError;
+
+pub const MODBUS_TCP_PORT : u16 = 502;
+const MBAP_HEADER_LEN : usize = 7;
+const MODBUS_PENDING_MAX : usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ModbusFunction {
+    ReadCoils,
+    ReadDiscreteInputs,
+    #[default]
+    ReadHoldingRegisters,
+    ReadInputRegisters,
+    WriteSingleCoil,
+    WriteSingleRegister,
+    WriteMultipleCoils,
+    WriteMultipleRegisters,
+}
+
+impl ModbusFunction {
+    pub const ALL: [ModbusFunction; 8] = [ModbusFunction::ReadCoils, ModbusFunction::ReadDiscreteInputs, ModbusFunction::ReadHoldingRegisters,
+        ModbusFunction::ReadInputRegisters, ModbusFunction::WriteSingleCoil, ModbusFunction::WriteSingleRegister,
+        ModbusFunction::WriteMultipleCoils, ModbusFunction::WriteMultipleRegisters];
+
+    pub fn code(&self) -> u8 {
+        match self {
+            ModbusFunction::ReadCoils => 0x01,
+            ModbusFunction::ReadDiscreteInputs => 0x02,
+            ModbusFunction::ReadHoldingRegisters => 0x03,
+            ModbusFunction::ReadInputRegisters => 0x04,
+            ModbusFunction::WriteSingleCoil => 0x05,
+            ModbusFunction::WriteSingleRegister => 0x06,
+            ModbusFunction::WriteMultipleCoils => 0x0F,
+            ModbusFunction::WriteMultipleRegisters => 0x10,
+        }
+    }
+
+    pub fn from_code(code:u8) -> Option<ModbusFunction> {
+        ModbusFunction::ALL.iter().find(|f| f.code() == code).cloned()
+    }
+
+    pub fn is_write(&self) -> bool {
+        match self {
+            ModbusFunction::ReadCoils | ModbusFunction::ReadDiscreteInputs
+                | ModbusFunction::ReadHoldingRegisters | ModbusFunction::ReadInputRegisters => false,
+            ModbusFunction::WriteSingleCoil | ModbusFunction::WriteSingleRegister
+                | ModbusFunction::WriteMultipleCoils | ModbusFunction::WriteMultipleRegisters => true,
+        }
+    }
+
+    pub fn is_bits(&self) -> bool {
+        match self {
+            ModbusFunction::ReadCoils | ModbusFunction::ReadDiscreteInputs
+                | ModbusFunction::WriteSingleCoil | ModbusFunction::WriteMultipleCoils => true,
+            ModbusFunction::ReadHoldingRegisters | ModbusFunction::ReadInputRegisters
+                | ModbusFunction::WriteSingleRegister | ModbusFunction::WriteMultipleRegisters => false,
+        }
+    }
+
+    pub fn quantity_max(&self) -> u16 {
+        match self {
+            ModbusFunction::ReadCoils | ModbusFunction::ReadDiscreteInputs => 2000,
+            ModbusFunction::ReadHoldingRegisters | ModbusFunction::ReadInputRegisters => 125,
+            ModbusFunction::WriteSingleCoil | ModbusFunction::WriteSingleRegister => 1,
+            ModbusFunction::WriteMultipleCoils => 1968,
+            ModbusFunction::WriteMultipleRegisters => 123,
+        }
+    }
+}
+
+impl fmt::Display for ModbusFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ModbusFunction::ReadCoils => "读线圈",
+            ModbusFunction::ReadDiscreteInputs => "读离散输入",
+            ModbusFunction::ReadHoldingRegisters => "读保持寄存器",
+            ModbusFunction::ReadInputRegisters => "读输入寄存器",
+            ModbusFunction::WriteSingleCoil => "写单个线圈",
+            ModbusFunction::WriteSingleRegister => "写单个寄存器",
+            ModbusFunction::WriteMultipleCoils => "写多个线圈",
+            ModbusFunction::WriteMultipleRegisters => "写多个寄存器",
+        };
+        write!(f, "{:02X} {}", self.code(), name)
+    }
+}
+
+pub fn exception_name(code:u8) -> &'static str {
+    match code {
+        0x01 => "非法功能",
+        0x02 => "非法数据地址",
+        0x03 => "非法数据值",
+        0x04 => "从站设备故障",
+        0x05 => "确认",
+        0x06 => "从站设备忙",
+        0x08 => "存储奇偶性差错",
+        0x0A => "网关路径不可用",
+        0x0B => "网关目标设备响应失败",
+        _ => "未知异常"
+    }
+}
+
+fn read_u16(buffer:&[u8], index:usize) -> u16 {
+    u16::from_be_bytes([buffer[index], buffer[index + 1]])
+}
+
+pub fn parse_number(name:&str, input:&str) -> Result<u16, RokitError> {
+    let input = input.trim();
+    let res = match input.strip_prefix("0x").or_else(|| input.strip_prefix("0X")) {
+        Some(hex) => u16::from_str_radix(hex, 16),
+        None => input.parse::<u16>()
+    };
+    match res {
+        Ok(x) => Ok(x),
+        Err(_) => Err(RokitError::decode(format!("{}格式错误:{}", name, input)))
+    }
+}
+
+pub fn parse_values(bits:bool, input:&str) -> Result<Vec<u16>, RokitError> {
+    let mut values = Vec::new();
+    for value in input.split(|c:char| c == ',' || c.is_whitespace()).filter(|s| !s.is_empty()) {
+        let value = parse_number("写入值", value)?;
+        if bits && value > 1 {
+            return Err(RokitError::decode(format!("线圈值只能是0或1:{}", value)));
+        }
+        values.push(value);
+    }
+    Ok(values)
+}
+
+pub fn parse_unit_id(input:&str) -> Result<u8, RokitError> {
+    match parse_number("从站地址", input)? {
+        x if x <= u8::MAX as u16 => Ok(x as u8),
+        _ => Err(RokitError::decode(format!("从站地址格式错误:{}", input)))
+    }
+}
+
+// MBAP header: transaction id, protocol id (always 0), length of unit id + PDU, unit id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModbusFrame {
+    pub transaction_id:u16,
+    pub unit_id:u8,
+    pub pdu:Vec<u8>,
+}
+
+impl ModbusFrame {
+    pub fn framing() -> Framing {
+        Framing{
+            mode:FramingMode::LengthPrefixed,
+            length_size:2,
+            big_endian:true,
+            length_offset:4,
+            length_adjustment:0,
+            ..Framing::default()
+        }
+    }
+
+    pub fn parse(frame:&[u8]) -> Result<ModbusFrame, RokitError> {
+        if frame.len() <= MBAP_HEADER_LEN {
+            return Err(RokitError::decode(format!("Modbus帧太短:{}字节", frame.len())));
+        }
+        if read_u16(frame, 2) != 0 {
+            return Err(RokitError::decode(format!("Modbus协议标识错误:{}", read_u16(frame, 2))));
+        }
+        if read_u16(frame, 4) as usize != frame.len() - 6 {
+            return Err(RokitError::decode(format!("Modbus长度错误:{}", read_u16(frame, 4))));
+        }
+        Ok(ModbusFrame{
+            transaction_id:read_u16(frame, 0),
+            unit_id:frame[6],
+            pdu:frame[MBAP_HEADER_LEN..].to_vec(),
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buffer = Vec::with_capacity(MBAP_HEADER_LEN + self.pdu.len());
+        buffer.extend_from_slice(&self.transaction_id.to_be_bytes());
+        buffer.extend_from_slice(&[0, 0]);
+        buffer.extend_from_slice(&(self.pdu.len() as u16 + 1).to_be_bytes());
+        buffer.push(self.unit_id);
+        buffer.extend_from_slice(&self.pdu);
+        buffer
+    }
+}
+
+// Coil values are 0 or 1. For single writes `quantity` is 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModbusRequest {
+    pub function:ModbusFunction,
+    pub address:u16,
+    pub quantity:u16,
+    pub values:Vec<u16>,
+}
+
+impl ModbusRequest {
+    pub fn new(function:ModbusFunction, address:&str, quantity:&str, values:&str) -> Result<Self, RokitError> {
+        let address = parse_number("起始地址", address)?;
+        let values = if function.is_write() {
+            parse_values(function.is_bits(), values)?
+        } else {
+            Vec::new()
+        };
+        let quantity = if function.is_write() {
+            values.len() as u16
+        } else {
+            parse_number("数量", quantity)?
+        };
+        if quantity == 0 || quantity > function.quantity_max() {
+            return Err(RokitError::decode(format!("{}数量应为1到{}:{}", function, function.quantity_max(), quantity)));
+        }
+        if address as u32 + quantity as u32 > 0x10000 {
+            return Err(RokitError::decode(format!("地址超出范围:{}+{}", address, quantity)));
+        }
+        Ok(ModbusRequest{ function, address, quantity, values })
+    }
+
+    // Errors are the exception code to answer with.
+    pub fn parse_pdu(pdu:&[u8]) -> Result<ModbusRequest, u8> {
+        let function = match pdu.first().and_then(|code| ModbusFunction::from_code(*code)) {
+            Some(x) => x,
+            None => return Err(0x01)
+        };
+        if pdu.len() < 5 {
+            return Err(0x03);
+        }
+        let address = read_u16(pdu, 1);
+        let field = read_u16(pdu, 3);
+        let (quantity, values) = match function {
+            ModbusFunction::ReadCoils | ModbusFunction::ReadDiscreteInputs
+                | ModbusFunction::ReadHoldingRegisters | ModbusFunction::ReadInputRegisters if pdu.len() == 5 => (field, Vec::new()),
+            ModbusFunction::WriteSingleCoil if pdu.len() == 5 => match field {
+                0xFF00 => (1, vec![1]),
+                0x0000 => (1, vec![0]),
+                _ => return Err(0x03)
+            },
+            ModbusFunction::WriteSingleRegister if pdu.len() == 5 => (1, vec![field]),
+            ModbusFunction::WriteMultipleCoils => {
+                let count = (field as usize).div_ceil(8);
+                if pdu.len() != 6 + count || pdu[5] as usize != count {
+                    return Err(0x03);
+                }
+                (field, (0..field as usize).map(|i| (pdu[6 + i / 8] >> (i % 8) & 1) as u16).collect())
+            },
+            ModbusFunction::WriteMultipleRegisters => {
+                let count = field as usize * 2;
+                if pdu.len() != 6 + count || pdu[5] as usize != count {
+                    return Err(0x03);
+                }
+                (field, (0..field as usize).map(|i| read_u16(pdu, 6 + i * 2)).collect())
+            },
+            _ => return Err(0x03)
+        };
+        if quantity == 0 || quantity > function.quantity_max() {
+            return Err(0x03);
+        }
+        if address as u32 + quantity as u32 > 0x10000 {
+            return Err(0x02);
+        }
+        Ok(ModbusRequest{ function, address, quantity, values })
+    }
+
+    pub fn to_pdu(&self) -> Vec<u8> {
+        let mut pdu = vec![self.function.code()];
+        pdu.extend_from_slice(&self.address.to_be_bytes());
+        match self.function {
+            ModbusFunction::ReadCoils | ModbusFunction::ReadDiscreteInputs
+                | ModbusFunction::ReadHoldingRegisters | ModbusFunction::ReadInputRegisters => {
+                pdu.extend_from_slice(&self.quantity.to_be_bytes());
+            },
+            ModbusFunction::WriteSingleCoil => {
+                let value : u16 = if self.values[0] != 0 { 0xFF00 } else { 0x0000 };
+                pdu.extend_from_slice(&value.to_be_bytes());
+            },
+            ModbusFunction::WriteSingleRegister => pdu.extend_from_slice(&self.values[0].to_be_bytes()),
+            ModbusFunction::WriteMultipleCoils => {
+                let mut bits = vec![0u8; (self.values.len()).div_ceil(8)];
+                for (i, value) in self.values.iter().enumerate() {
+                    if *value != 0 {
+                        bits[i / 8] |= 1 << (i % 8);
+                    }
+                }
+                pdu.extend_from_slice(&self.quantity.to_be_bytes());
+                pdu.push(bits.len() as u8);
+                pdu.extend_from_slice(&bits);
+            },
+            ModbusFunction::WriteMultipleRegisters => {
+                pdu.extend_from_slice(&self.quantity.to_be_bytes());
+                pdu.push((self.values.len() * 2) as u8);
+                for value in self.values.iter() {
+                    pdu.extend_from_slice(&value.to_be_bytes());
+                }
+            },
+        }
+        pdu
+    }
+
+    pub fn describe(&self) -> String {
+        if self.function.is_write() {
+            let values : Vec<String> = self.values.iter().map(|v| v.to_string()).collect();
+            format!("{} 地址{}: {}", self.function, self.address, values.join(" "))
+        } else {
+            format!("{} 地址{} 数量{}", self.function, self.address, self.quantity)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModbusResponse {
+    Bits(Vec<bool>),
+    Registers(Vec<u16>),
+    Written(u16, u16),
+    Exception(u8),
+}
+
+impl ModbusResponse {
+    pub fn parse(request:&ModbusRequest, pdu:&[u8]) -> Result<ModbusResponse, RokitError> {
+        let code = request.function.code();
+        match pdu.first() {
+            Some(x) if *x == code | 0x80 => {
+                return match pdu.get(1) {
+                    Some(exception) if pdu.len() == 2 => Ok(ModbusResponse::Exception(*exception)),
+                    _ => Err(RokitError::decode("Modbus异常响应长度错误".to_string()))
+                };
+            },
+            Some(x) if *x == code => {},
+            Some(x) => return Err(RokitError::decode(format!("Modbus功能码不符:{:02X}", x))),
+            None => return Err(RokitError::decode("Modbus响应为空".to_string()))
+        }
+        let quantity = request.quantity as usize;
+        match request.function {
+            ModbusFunction::ReadCoils | ModbusFunction::ReadDiscreteInputs => {
+                let count = quantity.div_ceil(8);
+                if pdu.len() != 2 + count || pdu[1] as usize != count {
+                    return Err(RokitError::decode(format!("Modbus响应长度错误:{}字节", pdu.len())));
+                }
+                Ok(ModbusResponse::Bits((0..quantity).map(|i| pdu[2 + i / 8] >> (i % 8) & 1 == 1).collect()))
+            },
+            ModbusFunction::ReadHoldingRegisters | ModbusFunction::ReadInputRegisters => {
+                if pdu.len() != 2 + quantity * 2 || pdu[1] as usize != quantity * 2 {
+                    return Err(RokitError::decode(format!("Modbus响应长度错误:{}字节", pdu.len())));
+                }
+                Ok(ModbusResponse::Registers((0..quantity).map(|i| read_u16(pdu, 2 + i * 2)).collect()))
+            },
+            ModbusFunction::WriteSingleCoil | ModbusFunction::WriteSingleRegister
+                | ModbusFunction::WriteMultipleCoils | ModbusFunction::WriteMultipleRegisters => {
+                if pdu.len() != 5 {
+                    return Err(RokitError::decode(format!("Modbus响应长度错误:{}字节", pdu.len())));
+                }
+                if pdu[1..] != request.to_pdu()[1..5] {
+                    return Err(RokitError::decode(format!("Modbus写入确认与请求不符:{}", common::bytes_to_hex(pdu))));
+                }
+                Ok(ModbusResponse::Written(read_u16(pdu, 1), read_u16(pdu, 3)))
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ModbusReply {
+    pub transaction_id:u16,
+    pub unit_id:u8,
+    pub request:ModbusRequest,
+    pub response:ModbusResponse,
+}
+
+impl ModbusReply {
+    pub fn is_exception(&self) -> bool {
+        matches!(self.response, ModbusResponse::Exception(_))
+    }
+
+    pub fn describe(&self) -> String {
+        let body = match self.response {
+            ModbusResponse::Bits(ref bits) => {
+                let values : Vec<&str> = bits.iter().map(|b| if *b { "1" } else { "0" }).collect();
+                format!("{} 地址{}: {}", self.request.function, self.request.address, values.join(" "))
+            },
+            ModbusResponse::Registers(ref registers) => {
+                let values : Vec<String> = registers.iter().map(|v| v.to_string()).collect();
+                format!("{} 地址{}: {}", self.request.function, self.request.address, values.join(" "))
+            },
+            ModbusResponse::Written(address, value) if self.request.function == ModbusFunction::WriteSingleCoil => {
+                format!("{} 地址{} 确认{}", self.request.function, address, if value == 0xFF00 { 1 } else { 0 })
+            },
+            ModbusResponse::Written(address, value) => format!("{} 地址{} 确认{}", self.request.function, address, value),
+            ModbusResponse::Exception(code) => format!("{} 异常{:02X} {}", self.request.function, code, exception_name(code)),
+        };
+        format!("Modbus响应#{} 从站{} {}", self.transaction_id, self.unit_id, body)
+    }
+}
+
+// Requests are matched to responses by transaction id; the oldest pending
+// requests are dropped when the device never answers.
+#[derive(Debug, Default)]
+pub struct ModbusClient {
+    next_transaction_id:u16,
+    pending:Vec<(u16, u8, ModbusRequest)>,
+}
+
+impl ModbusClient {
+    pub fn new() -> Self {
+        ModbusClient::default()
+    }
+
+    pub fn request(&mut self, unit_id:u8, request:ModbusRequest) -> (u16, Vec<u8>) {
+        let transaction_id = self.next_transaction_id;
+        self.next_transaction_id = self.next_transaction_id.wrapping_add(1);
+        let frame = ModbusFrame{ transaction_id, unit_id, pdu:request.to_pdu() };
+        self.pending.retain(|(id, _, _)| *id != transaction_id);
+        self.pending.push((transaction_id, unit_id, request));
+        if self.pending.len() > MODBUS_PENDING_MAX {
+            self.pending.remove(0);
+        }
+        (transaction_id, frame.to_bytes())
+    }
+
+    pub fn response(&mut self, buffer:&[u8]) -> Result<ModbusReply, RokitError> {
+        let frame = ModbusFrame::parse(buffer)?;
+        let index = match self.pending.iter().position(|(id, _, _)| *id == frame.transaction_id) {
+            Some(x) => x,
+            None => return Err(RokitError::decode(format!("Modbus未知事务号:{}", frame.transaction_id)))
+        };
+        let (transaction_id, unit_id, request) = self.pending.remove(index);
+        if unit_id != frame.unit_id {
+            return Err(RokitError::decode(format!("Modbus从站地址不符:{} 请求{}", frame.unit_id, unit_id)));
+        }
+        let response = ModbusResponse::parse(&request, &frame.pdu)?;
+        Ok(ModbusReply{ transaction_id, unit_id, request, response })
+    }
+
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ModbusTable {
+    Coils,
+    DiscreteInputs,
+    #[default]
+    HoldingRegisters,
+    InputRegisters,
+}
+
+impl ModbusTable {
+    pub const ALL: [ModbusTable; 4] = [ModbusTable::Coils, ModbusTable::DiscreteInputs, ModbusTable::HoldingRegisters, ModbusTable::InputRegisters];
+
+    pub fn is_bits(&self) -> bool {
+        match self {
+            ModbusTable::Coils | ModbusTable::DiscreteInputs => true,
+            ModbusTable::HoldingRegisters | ModbusTable::InputRegisters => false,
+        }
+    }
+}
+
+impl fmt::Display for ModbusTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ModbusTable::Coils => "线圈",
+            ModbusTable::DiscreteInputs => "离散输入",
+            ModbusTable::HoldingRegisters => "保持寄存器",
+            ModbusTable::InputRegisters => "输入寄存器",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// Every table covers the full 16-bit address space, bits are stored as 0 or 1.
+#[derive(Debug, Clone)]
+pub struct ModbusRegisters {
+    coils:Vec<u16>,
+    discrete_inputs:Vec<u16>,
+    holding_registers:Vec<u16>,
+    input_registers:Vec<u16>,
+}
+
+impl Default for ModbusRegisters {
+    fn default() -> Self {
+        ModbusRegisters{
+            coils:vec![0; 0x10000],
+            discrete_inputs:vec![0; 0x10000],
+            holding_registers:vec![0; 0x10000],
+            input_registers:vec![0; 0x10000],
+        }
+    }
+}
+
+impl ModbusRegisters {
+    pub fn new() -> Self {
+        ModbusRegisters::default()
+    }
+
+    fn table(&self, table:ModbusTable) -> &Vec<u16> {
+        match table {
+            ModbusTable::Coils => &self.coils,
+            ModbusTable::DiscreteInputs => &self.discrete_inputs,
+            ModbusTable::HoldingRegisters => &self.holding_registers,
+            ModbusTable::InputRegisters => &self.input_registers,
+        }
+    }
+
+    fn table_mut(&mut self, table:ModbusTable) -> &mut Vec<u16> {
+        match table {
+            ModbusTable::Coils => &mut self.coils,
+            ModbusTable::DiscreteInputs => &mut self.discrete_inputs,
+            ModbusTable::HoldingRegisters => &mut self.holding_registers,
+            ModbusTable::InputRegisters => &mut self.input_registers,
+        }
+    }
+
+    pub fn get(&self, table:ModbusTable, address:u16, quantity:u16) -> &[u16] {
+        let start = address as usize;
+        let end = (start + quantity as usize).min(0x10000);
+        &self.table(table)[start..end]
+    }
+
+    pub fn set(&mut self, table:ModbusTable, address:u16, values:&[u16]) -> Result<(), RokitError> {
+        if address as usize + values.len() > 0x10000 {
+            return Err(RokitError::decode(format!("地址超出范围:{}+{}", address, values.len())));
+        }
+        if table.is_bits() && values.iter().any(|v| *v > 1) {
+            return Err(RokitError::decode(format!("{}值只能是0或1", table)));
+        }
+        let start = address as usize;
+        self.table_mut(table)[start..start + values.len()].copy_from_slice(values);
+        Ok(())
+    }
+
+    // Requests have already been range checked by `ModbusRequest::parse_pdu`.
+    pub fn apply(&mut self, request:&ModbusRequest) -> Vec<u8> {
+        let mut pdu = vec![request.function.code()];
+        match request.function {
+            ModbusFunction::ReadCoils | ModbusFunction::ReadDiscreteInputs => {
+                let table = if request.function == ModbusFunction::ReadCoils { ModbusTable::Coils } else { ModbusTable::DiscreteInputs };
+                let mut bits = vec![0u8; (request.quantity as usize).div_ceil(8)];
+                for (i, value) in self.get(table, request.address, request.quantity).iter().enumerate() {
+                    if *value != 0 {
+                        bits[i / 8] |= 1 << (i % 8);
+                    }
+                }
+                pdu.push(bits.len() as u8);
+                pdu.extend_from_slice(&bits);
+            },
+            ModbusFunction::ReadHoldingRegisters | ModbusFunction::ReadInputRegisters => {
+                let table = if request.function == ModbusFunction::ReadHoldingRegisters { ModbusTable::HoldingRegisters } else { ModbusTable::InputRegisters };
+                pdu.push((request.quantity * 2) as u8);
+                for value in self.get(table, request.address, request.quantity) {
+                    pdu.extend_from_slice(&value.to_be_bytes());
+                }
+            },
+            ModbusFunction::WriteSingleCoil | ModbusFunction::WriteMultipleCoils => {
+                let _ = self.set(ModbusTable::Coils, request.address, &request.values);
+                pdu = request.to_pdu()[..5].to_vec();
+            },
+            ModbusFunction::WriteSingleRegister | ModbusFunction::WriteMultipleRegisters => {
+                let _ = self.set(ModbusTable::HoldingRegisters, request.address, &request.values);
+                pdu = request.to_pdu()[..5].to_vec();
+            },
+        }
+        pdu
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ModbusFault {
+    #[default]
+    None,
+    Exception(u8),
+}
+
+impl ModbusFault {
+    pub const ALL: [ModbusFault; 7] = [ModbusFault::None, ModbusFault::Exception(0x01), ModbusFault::Exception(0x02),
+        ModbusFault::Exception(0x03), ModbusFault::Exception(0x04), ModbusFault::Exception(0x06), ModbusFault::Exception(0x0B)];
+}
+
+impl fmt::Display for ModbusFault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModbusFault::None => write!(f, "正常响应"),
+            ModbusFault::Exception(code) => write!(f, "异常{:02X} {}", code, exception_name(*code)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ModbusServed {
+    pub response:Vec<u8>,
+    pub message:String,
+    pub exception:Option<u8>,
+}
+
+#[derive(Debug, Default)]
+pub struct ModbusServer {
+    pub registers:ModbusRegisters,
+    pub fault:ModbusFault,
+}
+
+impl ModbusServer {
+    pub fn new() -> Self {
+        ModbusServer::default()
+    }
+
+    // Answers every unit id. An injected fault replaces the response to any valid request.
+    pub fn handle(&mut self, buffer:&[u8]) -> Result<ModbusServed, RokitError> {
+        let frame = ModbusFrame::parse(buffer)?;
+        let code = frame.pdu[0] & 0x7F;
+        let (pdu, describe, exception) = match ModbusRequest::parse_pdu(&frame.pdu) {
+            Ok(request) => match self.fault {
+                ModbusFault::Exception(exception) => (vec![code | 0x80, exception], request.describe(), Some(exception)),
+                ModbusFault::None => (self.registers.apply(&request), request.describe(), None)
+            },
+            Err(exception) => (vec![code | 0x80, exception], format!("功能码{:02X} [{}]", code, common::bytes_to_hex(&frame.pdu)), Some(exception))
+        };
+        let mut message = format!("Modbus请求#{} 从站{} {}", frame.transaction_id, frame.unit_id, describe);
+        if let Some(exception) = exception {
+            message = format!("{} 应答异常{:02X} {}", message, exception, exception_name(exception));
+        }
+        let response = ModbusFrame{ transaction_id:frame.transaction_id, unit_id:frame.unit_id, pdu }.to_bytes();
+        Ok(ModbusServed{ response, message, exception })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reply(transaction_id:u16, unit_id:u8, pdu:&[u8]) -> Vec<u8> {
+        ModbusFrame{ transaction_id, unit_id, pdu:pdu.to_vec() }.to_bytes()
+    }
+
+    #[test]
+    fn requests_encode_every_function() {
+        let cases : [(ModbusFunction, &str, &str, &str, Vec<u8>); 8] = [
+            (ModbusFunction::ReadCoils, "0x13", "37", "", vec![0x01, 0x00, 0x13, 0x00, 0x25]),
+            (ModbusFunction::ReadDiscreteInputs, "196", "22", "", vec![0x02, 0x00, 0xC4, 0x00, 0x16]),
+            (ModbusFunction::ReadHoldingRegisters, "107", "3", "", vec![0x03, 0x00, 0x6B, 0x00, 0x03]),
+            (ModbusFunction::ReadInputRegisters, "8", "1", "", vec![0x04, 0x00, 0x08, 0x00, 0x01]),
+            (ModbusFunction::WriteSingleCoil, "172", "", "1", vec![0x05, 0x00, 0xAC, 0xFF, 0x00]),
+            (ModbusFunction::WriteSingleRegister, "1", "", "3", vec![0x06, 0x00, 0x01, 0x00, 0x03]),
+            (ModbusFunction::WriteMultipleCoils, "19", "", "1 0 1 1 0 0 1 1 1 0", vec![0x0F, 0x00, 0x13, 0x00, 0x0A, 0x02, 0xCD, 0x01]),
+            (ModbusFunction::WriteMultipleRegisters, "1", "", "0x000A, 0x0102", vec![0x10, 0x00, 0x01, 0x00, 0x02, 0x04, 0x00, 0x0A, 0x01, 0x02]),
+        ];
+        for (function, address, quantity, values, pdu) in cases {
+            assert_eq!(ModbusRequest::new(function, address, quantity, values).unwrap().to_pdu(), pdu, "{}", function);
+        }
+        assert!(ModbusRequest::new(ModbusFunction::ReadHoldingRegisters, "0", "126", "").is_err());
+        assert!(ModbusRequest::new(ModbusFunction::WriteMultipleCoils, "0", "", "1 2").is_err());
+    }
+
+    #[test]
+    fn responses_decode() {
+        let mut client = ModbusClient::new();
+        let (id, buffer) = client.request(1, ModbusRequest::new(ModbusFunction::ReadCoils, "19", "10", "").unwrap());
+        assert_eq!(buffer, vec![0x00, 0x00, 0x00, 0x00, 0x00, 0x06, 0x01, 0x01, 0x00, 0x13, 0x00, 0x0A]);
+        let bits = vec![true, false, true, true, false, false, true, true, true, false];
+        assert_eq!(client.response(&reply(id, 1, &[0x01, 0x02, 0xCD, 0x01])).unwrap().response, ModbusResponse::Bits(bits));
+
+        let (id, _) = client.request(1, ModbusRequest::new(ModbusFunction::ReadHoldingRegisters, "107", "2", "").unwrap());
+        let registers = client.response(&reply(id, 1, &[0x03, 0x04, 0x02, 0x2B, 0x00, 0x64])).unwrap().response;
+        assert_eq!(registers, ModbusResponse::Registers(vec![0x022B, 0x0064]));
+
+        let (id, _) = client.request(1, ModbusRequest::new(ModbusFunction::WriteSingleCoil, "172", "", "1").unwrap());
+        assert_eq!(client.response(&reply(id, 1, &[0x05, 0x00, 0xAC, 0xFF, 0x00])).unwrap().response, ModbusResponse::Written(172, 0xFF00));
+
+        let (id, _) = client.request(1, ModbusRequest::new(ModbusFunction::WriteMultipleRegisters, "1", "", "10 258").unwrap());
+        assert!(client.response(&reply(id, 1, &[0x10, 0x00, 0x01, 0x00, 0x03])).is_err());
+
+        let (id, _) = client.request(1, ModbusRequest::new(ModbusFunction::ReadInputRegisters, "8", "1", "").unwrap());
+        let exception = client.response(&reply(id, 1, &[0x84, 0x02])).unwrap();
+        assert!(exception.is_exception());
+        assert_eq!(exception.response, ModbusResponse::Exception(0x02));
+
+        let (id, _) = client.request(1, ModbusRequest::new(ModbusFunction::ReadCoils, "0", "9", "").unwrap());
+        assert!(client.response(&reply(id, 1, &[0x01, 0x01, 0xFF])).is_err());
+    }
+
+    #[test]
+    fn responses_match_transactions() {
+        let mut client = ModbusClient::new();
+        let (first, _) = client.request(1, ModbusRequest::new(ModbusFunction::ReadHoldingRegisters, "0", "1", "").unwrap());
+        let (second, _) = client.request(2, ModbusRequest::new(ModbusFunction::ReadInputRegisters, "0", "1", "").unwrap());
+        assert_ne!(first, second);
+        assert_eq!(client.pending(), 2);
+        let reply_second = client.response(&reply(second, 2, &[0x04, 0x02, 0x00, 0x14])).unwrap();
+        assert_eq!((reply_second.transaction_id, reply_second.unit_id), (second, 2));
+        assert_eq!(reply_second.response, ModbusResponse::Registers(vec![20]));
+        assert!(client.response(&reply(second, 2, &[0x04, 0x02, 0x00, 0x14])).is_err());
+        assert!(client.response(&reply(first, 9, &[0x03, 0x02, 0x00, 0x0A])).is_err());
+        assert_eq!(client.pending(), 0);
+    }
+
+    fn round_trip(client:&mut ModbusClient, server:&mut ModbusServer, request:ModbusRequest) -> ModbusReply {
+        let (_, buffer) = client.request(7, request);
+        let served = server.handle(&buffer).unwrap();
+        client.response(&served.response).unwrap()
+    }
+
+    #[test]
+    fn every_function_round_trips() {
+        let mut client = ModbusClient::new();
+        let mut server = ModbusServer::new();
+        server.registers.set(ModbusTable::DiscreteInputs, 100, &[1, 0, 1]).unwrap();
+        server.registers.set(ModbusTable::InputRegisters, 200, &[0x1234, 0xABCD]).unwrap();
+
+        let request = ModbusRequest::new(ModbusFunction::WriteMultipleCoils, "3", "", "1 0 1 1 0 0 0 0 1 1").unwrap();
+        assert_eq!(request.to_pdu(), vec![0x0F, 0x00, 0x03, 0x00, 0x0A, 0x02, 0x0D, 0x03]);
+        assert_eq!(round_trip(&mut client, &mut server, request).response, ModbusResponse::Written(3, 10));
+        let request = ModbusRequest::new(ModbusFunction::WriteSingleCoil, "14", "", "1").unwrap();
+        assert_eq!(round_trip(&mut client, &mut server, request).response, ModbusResponse::Written(14, 0xFF00));
+        let request = ModbusRequest::new(ModbusFunction::ReadCoils, "3", "12", "").unwrap();
+        let bits = vec![true, false, true, true, false, false, false, false, true, true, false, true];
+        assert_eq!(round_trip(&mut client, &mut server, request).response, ModbusResponse::Bits(bits));
+        let request = ModbusRequest::new(ModbusFunction::ReadDiscreteInputs, "100", "3", "").unwrap();
+        assert_eq!(round_trip(&mut client, &mut server, request).response, ModbusResponse::Bits(vec![true, false, true]));
+
+        let request = ModbusRequest::new(ModbusFunction::WriteSingleRegister, "0x10", "", "0xBEEF").unwrap();
+        assert_eq!(round_trip(&mut client, &mut server, request).response, ModbusResponse::Written(0x10, 0xBEEF));
+        let request = ModbusRequest::new(ModbusFunction::WriteMultipleRegisters, "17", "", "1, 2, 65535").unwrap();
+        assert_eq!(round_trip(&mut client, &mut server, request).response, ModbusResponse::Written(17, 3));
+        let request = ModbusRequest::new(ModbusFunction::ReadHoldingRegisters, "16", "4", "").unwrap();
+        assert_eq!(round_trip(&mut client, &mut server, request).response, ModbusResponse::Registers(vec![0xBEEF, 1, 2, 65535]));
+        let request = ModbusRequest::new(ModbusFunction::ReadInputRegisters, "200", "2", "").unwrap();
+        assert_eq!(round_trip(&mut client, &mut server, request).response, ModbusResponse::Registers(vec![0x1234, 0xABCD]));
+        assert_eq!(client.pending(), 0);
+    }
+
+    #[test]
+    fn exceptions_round_trip() {
+        let mut client = ModbusClient::new();
+        let mut server = ModbusServer::new();
+        server.fault = ModbusFault::Exception(0x02);
+        let reply = round_trip(&mut client, &mut server, ModbusRequest::new(ModbusFunction::ReadCoils, "0", "8", "").unwrap());
+        assert!(reply.is_exception());
+        assert_eq!(reply.response, ModbusResponse::Exception(0x02));
+
+        server.fault = ModbusFault::None;
+        let request = ModbusFrame{ transaction_id:9, unit_id:1, pdu:vec![0x03, 0x00, 0x00, 0x00, 0x7E] }.to_bytes();
+        let served = server.handle(&request).unwrap();
+        assert_eq!(served.exception, Some(0x03));
+        assert_eq!(ModbusFrame::parse(&served.response).unwrap().pdu, vec![0x83, 0x03]);
+    }
+}
@@ -0,0 +1,373 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::net::{IpAddr, Ipv6Addr, SocketAddr, UdpSocket};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::rokit_error::RokitError;
+
+const LINKTYPE_ETHERNET : u16 = 1;
+const ETHERTYPE_IPV4 : u16 = 0x0800;
+const ETHERTYPE_IPV6 : u16 = 0x86DD;
+const IP_PROTOCOL_TCP : u8 = 6;
+const IP_PROTOCOL_UDP : u8 = 17;
+const TCP_SEGMENT_MAX : usize = 1460;
+const UDP_DATAGRAM_MAX : usize = 65507;
+const TCP_FIN : u8 = 0x01;
+const TCP_SYN : u8 = 0x02;
+const TCP_PSH : u8 = 0x08;
+const TCP_ACK : u8 = 0x10;
+const TCP_ISN_LOCAL : u32 = 0x1000_0000;
+const TCP_ISN_REMOTE : u32 = 0x2000_0000;
+const MAC_LOCAL : [u8; 6] = [0x02, 0x00, 0x00, 0x00, 0x00, 0x01];
+const MAC_REMOTE : [u8; 6] = [0x02, 0x00, 0x00, 0x00, 0x00, 0x02];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureProtocol {
+    Tcp,
+    Udp,
+}
+
+#[derive(Debug, Clone)]
+pub enum CaptureEvent {
+    Open(bool),
+    Data(bool, Vec<u8>),
+    Close,
+}
+
+#[derive(Debug, Clone)]
+pub struct CaptureEntry {
+    pub time:SystemTime,
+    pub protocol:CaptureProtocol,
+    pub local:SocketAddr,
+    pub remote:SocketAddr,
+    pub event:CaptureEvent,
+}
+
+// Payloads sent and received by a session, exported as pcapng with synthesized
+// Ethernet/IP/TCP or UDP headers. TCP sequence numbers follow the payload so
+// Wireshark can reassemble the stream; `Open(local_is_client)` adds a handshake.
+#[derive(Debug, Clone, Default)]
+pub struct Capture {
+    entries:Vec<CaptureEntry>,
+    payloads:usize,
+}
+
+struct TcpStreamState {
+    local_seq:u32,
+    remote_seq:u32,
+}
+
+struct Packet {
+    time:SystemTime,
+    data:Vec<u8>,
+}
+
+impl Capture {
+    pub fn new() -> Self {
+        Capture{ entries:Vec::new(), payloads:0 }
+    }
+
+    pub fn record(&mut self, protocol:CaptureProtocol, local:SocketAddr, remote:SocketAddr, event:CaptureEvent) {
+        if let CaptureEvent::Data(..) = event {
+            self.payloads += 1;
+        }
+        self.entries.push(CaptureEntry{
+            time:SystemTime::now(),
+            protocol,
+            local,
+            remote,
+            event,
+        });
+    }
+
+    pub fn entries(&self) -> &[CaptureEntry] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.payloads
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.payloads = 0;
+    }
+
+    pub fn write_pcapng(&self, path:&Path) -> Result<usize, RokitError> {
+        let file = match File::create(path) {
+            Ok(x) => x,
+            Err(e) => return Err(RokitError::io(format!("抓包文件错误:{}", path.display()).as_str(), e))
+        };
+        let packets = self.packets();
+        let mut writer = BufWriter::new(file);
+        let mut res = writer.write_all(&section_header_block());
+        if res.is_ok() {
+            res = writer.write_all(&interface_description_block());
+        }
+        for packet in packets.iter() {
+            if res.is_err() {
+                break;
+            }
+            res = writer.write_all(&enhanced_packet_block(packet));
+        }
+        if res.is_ok() {
+            res = writer.flush();
+        }
+        match res {
+            Ok(_) => Ok(packets.len()),
+            Err(e) => Err(RokitError::io(format!("抓包文件错误:{}", path.display()).as_str(), e))
+        }
+    }
+
+    fn packets(&self) -> Vec<Packet> {
+        let mut streams : HashMap<(SocketAddr, SocketAddr), TcpStreamState> = HashMap::new();
+        let mut packets = Vec::new();
+        for entry in self.entries.iter() {
+            let (local, remote) = normalize(entry.local, entry.remote);
+            let mut push = |outbound:bool, flags:u8, seq:u32, ack:u32, payload:&[u8]| {
+                let (src, dst) = if outbound { (local, remote) } else { (remote, local) };
+                let data = match entry.protocol {
+                    CaptureProtocol::Tcp => frame(outbound, src, dst, IP_PROTOCOL_TCP, &tcp_segment(src, dst, seq, ack, flags, payload)),
+                    CaptureProtocol::Udp => frame(outbound, src, dst, IP_PROTOCOL_UDP, &udp_datagram(src, dst, payload)),
+                };
+                packets.push(Packet{ time:entry.time, data });
+            };
+            match (entry.protocol, &entry.event) {
+                (CaptureProtocol::Udp, CaptureEvent::Data(outbound, payload)) => {
+                    for chunk in payload.chunks(UDP_DATAGRAM_MAX) {
+                        push(*outbound, 0, 0, 0, chunk);
+                    }
+                },
+                (CaptureProtocol::Udp, _) => {},
+                (CaptureProtocol::Tcp, CaptureEvent::Open(local_is_client)) => {
+                    let (client_isn, server_isn) = if *local_is_client { (TCP_ISN_LOCAL, TCP_ISN_REMOTE) } else { (TCP_ISN_REMOTE, TCP_ISN_LOCAL) };
+                    push(*local_is_client, TCP_SYN, client_isn, 0, &[]);
+                    push(!*local_is_client, TCP_SYN | TCP_ACK, server_isn, client_isn.wrapping_add(1), &[]);
+                    push(*local_is_client, TCP_ACK, client_isn.wrapping_add(1), server_isn.wrapping_add(1), &[]);
+                    streams.insert((local, remote), TcpStreamState{
+                        local_seq:TCP_ISN_LOCAL.wrapping_add(1),
+                        remote_seq:TCP_ISN_REMOTE.wrapping_add(1),
+                    });
+                },
+                (CaptureProtocol::Tcp, CaptureEvent::Data(outbound, payload)) => {
+                    let state = streams.entry((local, remote)).or_insert(TcpStreamState{
+                        local_seq:TCP_ISN_LOCAL,
+                        remote_seq:TCP_ISN_REMOTE,
+                    });
+                    for chunk in payload.chunks(TCP_SEGMENT_MAX) {
+                        if *outbound {
+                            push(true, TCP_PSH | TCP_ACK, state.local_seq, state.remote_seq, chunk);
+                            state.local_seq = state.local_seq.wrapping_add(chunk.len() as u32);
+                        } else {
+                            push(false, TCP_PSH | TCP_ACK, state.remote_seq, state.local_seq, chunk);
+                            state.remote_seq = state.remote_seq.wrapping_add(chunk.len() as u32);
+                        }
+                    }
+                },
+                (CaptureProtocol::Tcp, CaptureEvent::Close) => {
+                    if let Some(state) = streams.remove(&(local, remote)) {
+                        push(true, TCP_FIN | TCP_ACK, state.local_seq, state.remote_seq, &[]);
+                        push(false, TCP_FIN | TCP_ACK, state.remote_seq, state.local_seq.wrapping_add(1), &[]);
+                        push(true, TCP_ACK, state.local_seq.wrapping_add(1), state.remote_seq.wrapping_add(1), &[]);
+                    }
+                }
+            }
+        }
+        packets
+    }
+}
+
+// A socket bound to 0.0.0.0 or :: has no address of its own, use the one the
+// system routes `remote` from, keeping the port.
+pub fn route_local_addr(local:SocketAddr, remote:SocketAddr) -> SocketAddr {
+    if !local.ip().is_unspecified() {
+        return local;
+    }
+    let any : SocketAddr = if remote.is_ipv4() { ([0, 0, 0, 0], 0).into() } else { (Ipv6Addr::UNSPECIFIED, 0).into() };
+    let routed = UdpSocket::bind(any).and_then(|socket| {
+        socket.connect(remote)?;
+        socket.local_addr()
+    });
+    match routed {
+        Ok(addr) => SocketAddr::new(addr.ip(), local.port()),
+        Err(_) => local
+    }
+}
+
+// Both endpoints must share an address family, IPv4 peers of an IPv6 socket
+// are written as IPv4-mapped addresses.
+fn normalize(local:SocketAddr, remote:SocketAddr) -> (SocketAddr, SocketAddr) {
+    match (local.ip(), remote.ip()) {
+        (IpAddr::V4(l), IpAddr::V6(_)) => (SocketAddr::new(IpAddr::V6(l.to_ipv6_mapped()), local.port()), remote),
+        (IpAddr::V6(_), IpAddr::V4(r)) => (local, SocketAddr::new(IpAddr::V6(r.to_ipv6_mapped()), remote.port())),
+        _ => (local, remote)
+    }
+}
+
+fn checksum(data:&[u8], initial:u32) -> u16 {
+    let mut sum = initial;
+    for chunk in data.chunks(2) {
+        let word = if chunk.len() == 2 { u16::from_be_bytes([chunk[0], chunk[1]]) } else { u16::from_be_bytes([chunk[0], 0]) };
+        sum += word as u32;
+    }
+    while sum >> 16 != 0 {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+fn pseudo_header_sum(src:SocketAddr, dst:SocketAddr, protocol:u8, length:usize) -> u32 {
+    let mut header = Vec::new();
+    match (src.ip(), dst.ip()) {
+        (IpAddr::V4(s), IpAddr::V4(d)) => {
+            header.extend_from_slice(&s.octets());
+            header.extend_from_slice(&d.octets());
+            header.extend_from_slice(&[0, protocol]);
+            header.extend_from_slice(&(length as u16).to_be_bytes());
+        },
+        (s, d) => {
+            header.extend_from_slice(&ipv6(s).octets());
+            header.extend_from_slice(&ipv6(d).octets());
+            header.extend_from_slice(&(length as u32).to_be_bytes());
+            header.extend_from_slice(&[0, 0, 0, protocol]);
+        }
+    }
+    (!checksum(&header, 0)) as u32
+}
+
+fn ipv6(ip:IpAddr) -> Ipv6Addr {
+    match ip {
+        IpAddr::V4(x) => x.to_ipv6_mapped(),
+        IpAddr::V6(x) => x
+    }
+}
+
+fn tcp_segment(src:SocketAddr, dst:SocketAddr, seq:u32, ack:u32, flags:u8, payload:&[u8]) -> Vec<u8> {
+    let mut segment = Vec::with_capacity(20 + payload.len());
+    segment.extend_from_slice(&src.port().to_be_bytes());
+    segment.extend_from_slice(&dst.port().to_be_bytes());
+    segment.extend_from_slice(&seq.to_be_bytes());
+    segment.extend_from_slice(&ack.to_be_bytes());
+    segment.extend_from_slice(&[5 << 4, flags]);
+    segment.extend_from_slice(&0xffffu16.to_be_bytes());
+    segment.extend_from_slice(&[0, 0, 0, 0]);
+    segment.extend_from_slice(payload);
+    let sum = checksum(&segment, pseudo_header_sum(src, dst, IP_PROTOCOL_TCP, segment.len()));
+    segment[16..18].copy_from_slice(&sum.to_be_bytes());
+    segment
+}
+
+fn udp_datagram(src:SocketAddr, dst:SocketAddr, payload:&[u8]) -> Vec<u8> {
+    let length = 8 + payload.len();
+    let mut datagram = Vec::with_capacity(length);
+    datagram.extend_from_slice(&src.port().to_be_bytes());
+    datagram.extend_from_slice(&dst.port().to_be_bytes());
+    datagram.extend_from_slice(&(length as u16).to_be_bytes());
+    datagram.extend_from_slice(&[0, 0]);
+    datagram.extend_from_slice(payload);
+    let sum = match checksum(&datagram, pseudo_header_sum(src, dst, IP_PROTOCOL_UDP, length)) {
+        0 => 0xffff,
+        x => x
+    };
+    datagram[6..8].copy_from_slice(&sum.to_be_bytes());
+    datagram
+}
+
+fn frame(outbound:bool, src:SocketAddr, dst:SocketAddr, protocol:u8, transport:&[u8]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(54 + transport.len());
+    if outbound {
+        data.extend_from_slice(&MAC_REMOTE);
+        data.extend_from_slice(&MAC_LOCAL);
+    } else {
+        data.extend_from_slice(&MAC_LOCAL);
+        data.extend_from_slice(&MAC_REMOTE);
+    }
+    match (src.ip(), dst.ip()) {
+        (IpAddr::V4(s), IpAddr::V4(d)) => {
+            data.extend_from_slice(&ETHERTYPE_IPV4.to_be_bytes());
+            let mut header = Vec::with_capacity(20);
+            header.extend_from_slice(&[0x45, 0]);
+            header.extend_from_slice(&((20 + transport.len()) as u16).to_be_bytes());
+            header.extend_from_slice(&[0, 0, 0x40, 0, 64, protocol, 0, 0]);
+            header.extend_from_slice(&s.octets());
+            header.extend_from_slice(&d.octets());
+            let sum = checksum(&header, 0);
+            header[10..12].copy_from_slice(&sum.to_be_bytes());
+            data.extend_from_slice(&header);
+        },
+        (s, d) => {
+            data.extend_from_slice(&ETHERTYPE_IPV6.to_be_bytes());
+            data.extend_from_slice(&[0x60, 0, 0, 0]);
+            data.extend_from_slice(&(transport.len() as u16).to_be_bytes());
+            data.extend_from_slice(&[protocol, 64]);
+            data.extend_from_slice(&ipv6(s).octets());
+            data.extend_from_slice(&ipv6(d).octets());
+        }
+    }
+    data.extend_from_slice(transport);
+    data
+}
+
+fn block(block_type:u32, body:&[u8]) -> Vec<u8> {
+    let padding = (4 - body.len() % 4) % 4;
+    let total = (12 + body.len() + padding) as u32;
+    let mut data = Vec::with_capacity(total as usize);
+    data.extend_from_slice(&block_type.to_le_bytes());
+    data.extend_from_slice(&total.to_le_bytes());
+    data.extend_from_slice(body);
+    data.resize(data.len() + padding, 0);
+    data.extend_from_slice(&total.to_le_bytes());
+    data
+}
+
+fn section_header_block() -> Vec<u8> {
+    let mut body = Vec::new();
+    body.extend_from_slice(&0x1A2B_3C4Du32.to_le_bytes());
+    body.extend_from_slice(&1u16.to_le_bytes());
+    body.extend_from_slice(&0u16.to_le_bytes());
+    body.extend_from_slice(&(-1i64).to_le_bytes());
+    block(0x0A0D_0D0A, &body)
+}
+
+fn interface_description_block() -> Vec<u8> {
+    let mut body = Vec::new();
+    body.extend_from_slice(&LINKTYPE_ETHERNET.to_le_bytes());
+    body.extend_from_slice(&0u16.to_le_bytes());
+    body.extend_from_slice(&0u32.to_le_bytes());
+    block(0x0000_0001, &body)
+}
+
+fn enhanced_packet_block(packet:&Packet) -> Vec<u8> {
+    let micros = match packet.time.duration_since(UNIX_EPOCH) {
+        Ok(x) => x.as_micros() as u64,
+        Err(_) => 0
+    };
+    let mut body = Vec::with_capacity(20 + packet.data.len());
+    body.extend_from_slice(&0u32.to_le_bytes());
+    body.extend_from_slice(&((micros >> 32) as u32).to_le_bytes());
+    body.extend_from_slice(&(micros as u32).to_le_bytes());
+    body.extend_from_slice(&(packet.data.len() as u32).to_le_bytes());
+    body.extend_from_slice(&(packet.data.len() as u32).to_le_bytes());
+    body.extend_from_slice(&packet.data);
+    block(0x0000_0006, &body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unspecified_local_is_routed() {
+        let local : SocketAddr = "192.0.2.1:9000".parse().unwrap();
+        let remote : SocketAddr = "127.0.0.1:40000".parse().unwrap();
+        assert_eq!(route_local_addr(local, remote), local);
+        let local : SocketAddr = "0.0.0.0:9000".parse().unwrap();
+        assert_eq!(route_local_addr(local, remote), "127.0.0.1:9000".parse().unwrap());
+    }
+}
@@ -0,0 +1,120 @@
+use serde::{Deserialize, Serialize};
+
+use crate::checksum::Checksum;
+use crate::common::{LineEnding, DEFAULT_BUFFER_SIZE};
+use crate::config;
+use crate::framing::Framing;
+use crate::rokit_error::RokitError;
+use crate::text_encoding::TextEncoding;
+use crate::transport::Protocol;
+use crate::udp_client::{UDP_LOCAL_IP_ANY, UDP_LOCAL_PORT_ANY};
+
+const PROFILE_STORE_FILE : &str = "profiles.json";
+const PROFILE_RECENT_MAX : usize = 10;
+
+fn default_local_ip() -> String {
+    String::from(UDP_LOCAL_IP_ANY)
+}
+
+fn default_local_port() -> String {
+    String::from(UDP_LOCAL_PORT_ANY)
+}
+
+fn default_buffer_size() -> usize {
+    DEFAULT_BUFFER_SIZE
+}
+
+fn default_escape() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    #[serde(default)]
+    pub name:String,
+    pub protocol:Protocol,
+    pub ip:String,
+    pub port:String,
+    #[serde(default = "default_local_ip")]
+    pub local_ip:String,
+    #[serde(default = "default_local_port")]
+    pub local_port:String,
+    #[serde(default = "default_buffer_size")]
+    pub buffer_size:usize,
+    #[serde(default)]
+    pub encoding:TextEncoding,
+    #[serde(default)]
+    pub line_ending:LineEnding,
+    #[serde(default = "default_escape")]
+    pub escape:bool,
+    #[serde(default)]
+    pub hex_display:bool,
+    #[serde(default)]
+    pub framing:Framing,
+    #[serde(default)]
+    pub checksum:Checksum,
+}
+
+impl Profile {
+    pub fn label(&self) -> String {
+        format!("{} {}:{}", self.protocol.title(), self.ip, self.port)
+    }
+
+    fn same_target(&self, other:&Profile) -> bool {
+        self.protocol == other.protocol && self.ip == other.ip && self.port == other.port
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProfileStore {
+    #[serde(default)]
+    pub profiles:Vec<Profile>,
+    // Most recent first, the first entry is restored on startup.
+    #[serde(default)]
+    pub recent:Vec<Profile>,
+}
+
+impl ProfileStore {
+    pub fn load() -> Result<ProfileStore, RokitError> {
+        let path = config::config_path(PROFILE_STORE_FILE)?;
+        if !path.exists() {
+            return Ok(ProfileStore::default());
+        }
+        config::load_json(&path)
+    }
+
+    pub fn save(&self) -> Result<(), RokitError> {
+        config::save_json(&config::config_path(PROFILE_STORE_FILE)?, self)
+    }
+
+    pub fn get(&self, name:&str) -> Option<&Profile> {
+        self.profiles.iter().find(|p| p.name == name)
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.profiles.iter().map(|p| p.name.clone()).collect()
+    }
+
+    pub fn upsert(&mut self, profile:Profile) {
+        match self.profiles.iter_mut().find(|p| p.name == profile.name) {
+            Some(x) => *x = profile,
+            None => self.profiles.push(profile)
+        }
+    }
+
+    pub fn remove(&mut self, name:&str) -> bool {
+        let len = self.profiles.len();
+        self.profiles.retain(|p| p.name != name);
+        self.profiles.len() != len
+    }
+
+    pub fn touch_recent(&mut self, profile:Profile) {
+        self.recent.retain(|p| !p.same_target(&profile));
+        self.recent.insert(0, profile);
+        self.recent.truncate(PROFILE_RECENT_MAX);
+    }
+
+    pub fn last(&self) -> Option<&Profile> {
+        self.recent.first()
+    }
+}
@@ -0,0 +1,559 @@
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use chrono::DateTime;
+
+use crate::common;
+use crate::pcap::CaptureProtocol;
+use crate::rokit_error::RokitError;
+
+pub const REPLAY_RESPONSE_TIMEOUT : Duration = Duration::from_secs(5);
+
+const LINKTYPE_NULL : u32 = 0;
+const LINKTYPE_ETHERNET : u32 = 1;
+const LINKTYPE_RAW : u32 = 101;
+const LINKTYPE_LOOP : u32 = 108;
+const LINKTYPE_LINUX_SLL : u32 = 113;
+const LINKTYPE_IPV4 : u32 = 228;
+const LINKTYPE_IPV6 : u32 = 229;
+const LINKTYPE_LINUX_SLL2 : u32 = 276;
+
+#[derive(Debug, Clone)]
+pub struct ReplayMessage {
+    pub offset:Duration,
+    pub payload:Vec<u8>,
+}
+
+// Client messages to resend, loaded from a rokit JSON lines log (the "send"
+// records) or from one TCP/UDP stream of a pcap/pcapng capture. Logs do not
+// record the protocol, so `protocol` is only known for captures.
+#[derive(Debug, Clone)]
+pub struct Recording {
+    pub messages:Vec<ReplayMessage>,
+    pub description:String,
+    pub protocol:Option<CaptureProtocol>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReplayMode {
+    #[default]
+    Original,
+    Scaled,
+    WaitResponse,
+}
+
+impl ReplayMode {
+    pub const ALL: [ReplayMode; 3] = [ReplayMode::Original, ReplayMode::Scaled, ReplayMode::WaitResponse];
+}
+
+impl fmt::Display for ReplayMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ReplayMode::Original => "原始间隔",
+            ReplayMode::Scaled => "缩放间隔",
+            ReplayMode::WaitResponse => "等待响应",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ReplayAction {
+    Send(Vec<u8>, String),
+    Log(String),
+    Finished,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReplayRunner {
+    messages:Vec<ReplayMessage>,
+    mode:ReplayMode,
+    scale:f64,
+    index:usize,
+    started:Option<Instant>,
+    waiting:Option<Instant>,
+    finished:bool,
+}
+
+struct Frame {
+    time:Duration,
+    link_type:u32,
+    data:Vec<u8>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum StreamProtocol {
+    Tcp,
+    Udp,
+}
+
+struct Segment {
+    time:Duration,
+    protocol:StreamProtocol,
+    src:SocketAddr,
+    dst:SocketAddr,
+    seq:u32,
+    syn:bool,
+    ack:bool,
+    payload:Vec<u8>,
+}
+
+fn format_error(msg:&str) -> RokitError {
+    RokitError::decode("回放文件格式错误:".to_string() + msg)
+}
+
+impl Recording {
+    pub fn load(path:&Path, stream:usize) -> Result<Recording, RokitError> {
+        match std::fs::read(path) {
+            Ok(data) => Recording::parse(&data, stream),
+            Err(e) => Err(RokitError::io(format!("回放文件读取错误:{}", path.display()).as_str(), e))
+        }
+    }
+
+    pub fn parse(data:&[u8], stream:usize) -> Result<Recording, RokitError> {
+        let magic = match data.get(..4) {
+            Some(x) => [x[0], x[1], x[2], x[3]],
+            None => return Err(format_error("文件为空"))
+        };
+        let frames = match magic {
+            [0x0A, 0x0D, 0x0D, 0x0A] => read_pcapng(data)?,
+            [0xA1, 0xB2, 0xC3, 0xD4] | [0xD4, 0xC3, 0xB2, 0xA1]
+            | [0xA1, 0xB2, 0x3C, 0x4D] | [0x4D, 0x3C, 0xB2, 0xA1] => read_pcap(data)?,
+            _ => return Recording::parse_log(data)
+        };
+        Recording::from_frames(frames, stream)
+    }
+
+    fn parse_log(data:&[u8]) -> Result<Recording, RokitError> {
+        let text = String::from_utf8_lossy(data);
+        let mut first = None;
+        let mut messages = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let value : serde_json::Value = match serde_json::from_str(line) {
+                Ok(x) => x,
+                Err(_) => return Err(format_error(format!("第{}行不是JSON日志", index + 1).as_str()))
+            };
+            if value["direction"].as_str() != Some("send") {
+                continue;
+            }
+            let (time, bytes) = match (value["time"].as_str(), value["bytes"].as_str()) {
+                (Some(time), Some(bytes)) => (time, bytes),
+                _ => return Err(format_error(format!("第{}行缺少time或bytes", index + 1).as_str()))
+            };
+            let time = match DateTime::parse_from_rfc3339(time) {
+                Ok(x) => x,
+                Err(_) => return Err(format_error(format!("第{}行时间错误", index + 1).as_str()))
+            };
+            let first = *first.get_or_insert(time);
+            let offset = match (time - first).to_std() {
+                Ok(x) => x,
+                Err(_) => Duration::ZERO
+            };
+            messages.push(ReplayMessage{ offset, payload:common::parse_hex(bytes.to_string())? });
+        }
+        let description = format!("日志{}条发送", messages.len());
+        Ok(Recording{ messages, description, protocol:None })
+    }
+
+    fn from_frames(frames:Vec<Frame>, stream:usize) -> Result<Recording, RokitError> {
+        let segments : Vec<Segment> = frames.iter().filter_map(decode_frame).collect();
+        let mut streams : Vec<(StreamProtocol, SocketAddr, SocketAddr)> = Vec::new();
+        for segment in segments.iter() {
+            let key = stream_key(segment);
+            if !streams.contains(&key) {
+                streams.push(key);
+            }
+        }
+        let key = match streams.get(stream) {
+            Some(x) => *x,
+            None => return Err(format_error(format!("没有第{}个流, 共{}个TCP/UDP流", stream, streams.len()).as_str()))
+        };
+        let stream_segments : Vec<&Segment> = segments.iter().filter(|s| stream_key(s) == key).collect();
+        let client = match stream_segments.iter().find(|s| s.syn && !s.ack) {
+            Some(x) => x.src,
+            None => stream_segments[0].src
+        };
+        let server = if client == key.1 { key.2 } else { key.1 };
+        let mut first = None;
+        let mut next_seq : Option<u32> = None;
+        let mut messages = Vec::new();
+        for segment in stream_segments {
+            if segment.src != client {
+                continue;
+            }
+            if segment.syn {
+                next_seq = Some(segment.seq.wrapping_add(1));
+                continue;
+            }
+            if segment.payload.is_empty() {
+                continue;
+            }
+            let mut payload = segment.payload.as_slice();
+            if key.0 == StreamProtocol::Tcp {
+                let expected = *next_seq.get_or_insert(segment.seq);
+                let overlap = expected.wrapping_sub(segment.seq) as i32;
+                if overlap > 0 {
+                    if overlap as usize >= payload.len() {
+                        continue;
+                    }
+                    payload = &payload[overlap as usize..];
+                }
+                next_seq = Some(segment.seq.wrapping_add(segment.payload.len() as u32));
+            }
+            let first = *first.get_or_insert(segment.time);
+            messages.push(ReplayMessage{
+                offset:segment.time.saturating_sub(first),
+                payload:payload.to_vec(),
+            });
+        }
+        let (name, protocol) = match key.0 {
+            StreamProtocol::Tcp => ("TCP", CaptureProtocol::Tcp),
+            StreamProtocol::Udp => ("UDP", CaptureProtocol::Udp),
+        };
+        let description = format!("{}流{} {} -> {}, {}条发送", name, stream, client, server, messages.len());
+        Ok(Recording{ messages, description, protocol:Some(protocol) })
+    }
+}
+
+fn stream_key(segment:&Segment) -> (StreamProtocol, SocketAddr, SocketAddr) {
+    if segment.src <= segment.dst {
+        (segment.protocol, segment.src, segment.dst)
+    } else {
+        (segment.protocol, segment.dst, segment.src)
+    }
+}
+
+struct Reader<'a> {
+    data:&'a [u8],
+    big_endian:bool,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&self, offset:usize, len:usize) -> Result<&'a [u8], RokitError> {
+        match self.data.get(offset..offset + len) {
+            Some(x) => Ok(x),
+            None => Err(format_error("抓包文件不完整"))
+        }
+    }
+
+    fn u16(&self, offset:usize) -> Result<u16, RokitError> {
+        let b = self.bytes(offset, 2)?;
+        Ok(if self.big_endian { u16::from_be_bytes([b[0], b[1]]) } else { u16::from_le_bytes([b[0], b[1]]) })
+    }
+
+    fn u32(&self, offset:usize) -> Result<u32, RokitError> {
+        let b = self.bytes(offset, 4)?;
+        Ok(if self.big_endian { u32::from_be_bytes([b[0], b[1], b[2], b[3]]) } else { u32::from_le_bytes([b[0], b[1], b[2], b[3]]) })
+    }
+}
+
+fn read_pcap(data:&[u8]) -> Result<Vec<Frame>, RokitError> {
+    let big_endian = data[0] == 0xA1;
+    let nanos = data[2..4] == [0x3C, 0x4D] || data[0..2] == [0x4D, 0x3C];
+    let reader = Reader{ data, big_endian };
+    let link_type = reader.u32(20)? & 0x0FFF_FFFF;
+    let mut frames = Vec::new();
+    let mut offset = 24;
+    while offset + 16 <= data.len() {
+        let seconds = reader.u32(offset)? as u64;
+        let fraction = reader.u32(offset + 4)? as u64;
+        let length = reader.u32(offset + 8)? as usize;
+        let time = if nanos { Duration::new(seconds, fraction as u32) } else { Duration::from_micros(seconds * 1_000_000 + fraction) };
+        frames.push(Frame{ time, link_type, data:reader.bytes(offset + 16, length)?.to_vec() });
+        offset += 16 + length;
+    }
+    Ok(frames)
+}
+
+fn read_pcapng(data:&[u8]) -> Result<Vec<Frame>, RokitError> {
+    let mut frames = Vec::new();
+    let mut reader = Reader{ data, big_endian:false };
+    let mut interfaces : Vec<(u32, u64)> = Vec::new();
+    let mut offset = 0;
+    while offset + 12 <= data.len() {
+        if data[offset..offset + 4] == [0x0A, 0x0D, 0x0D, 0x0A] {
+            reader.big_endian = reader.bytes(offset + 8, 4)? == [0x1A, 0x2B, 0x3C, 0x4D];
+            interfaces.clear();
+        }
+        let block_type = reader.u32(offset)?;
+        let length = reader.u32(offset + 4)? as usize;
+        if length < 12 || offset + length > data.len() {
+            return Err(format_error("抓包文件不完整"));
+        }
+        match block_type {
+            0x0000_0001 => {
+                let link_type = reader.u16(offset + 8)? as u32;
+                let mut units = 1_000_000u64;
+                let mut option = offset + 16;
+                while option + 4 <= offset + length - 4 {
+                    let code = reader.u16(option)?;
+                    let option_length = reader.u16(option + 2)? as usize;
+                    if code == 0 {
+                        break;
+                    }
+                    if code == 9 && option_length == 1 {
+                        let resolution = reader.bytes(option + 4, 1)?[0];
+                        let exponent = (resolution & 0x7F) as u32;
+                        units = if resolution & 0x80 != 0 { 2u64.saturating_pow(exponent) } else { 10u64.saturating_pow(exponent) };
+                    }
+                    option += 4 + option_length.div_ceil(4) * 4;
+                }
+                interfaces.push((link_type, units.max(1)));
+            },
+            0x0000_0006 => {
+                let interface = reader.u32(offset + 8)? as usize;
+                let (link_type, units) = match interfaces.get(interface) {
+                    Some(x) => *x,
+                    None => return Err(format_error("抓包接口不存在"))
+                };
+                let timestamp = ((reader.u32(offset + 12)? as u64) << 32) | reader.u32(offset + 16)? as u64;
+                let captured = reader.u32(offset + 20)? as usize;
+                let time = Duration::from_secs(timestamp / units)
+                    + Duration::from_nanos(((timestamp % units) as u128 * 1_000_000_000 / units as u128) as u64);
+                frames.push(Frame{ time, link_type, data:reader.bytes(offset + 28, captured)?.to_vec() });
+            },
+            _ => {}
+        }
+        offset += length;
+    }
+    Ok(frames)
+}
+
+fn decode_frame(frame:&Frame) -> Option<Segment> {
+    let data = frame.data.as_slice();
+    let packet = match frame.link_type {
+        LINKTYPE_ETHERNET => {
+            let mut offset = 12;
+            let mut ethertype = u16::from_be_bytes([*data.get(offset)?, *data.get(offset + 1)?]);
+            while ethertype == 0x8100 || ethertype == 0x88A8 {
+                offset += 4;
+                ethertype = u16::from_be_bytes([*data.get(offset)?, *data.get(offset + 1)?]);
+            }
+            data.get(offset + 2..)?
+        },
+        LINKTYPE_NULL | LINKTYPE_LOOP => data.get(4..)?,
+        LINKTYPE_RAW | LINKTYPE_IPV4 | LINKTYPE_IPV6 => data,
+        LINKTYPE_LINUX_SLL => data.get(16..)?,
+        LINKTYPE_LINUX_SLL2 => data.get(20..)?,
+        _ => return None
+    };
+    let (protocol, src_ip, dst_ip, transport) = match packet.first()? >> 4 {
+        4 => {
+            let header = ((packet[0] & 0x0F) as usize) * 4;
+            if packet.len() < 20 || header < 20 {
+                return None;
+            }
+            let total = u16::from_be_bytes([packet[2], packet[3]]) as usize;
+            let src = Ipv4Addr::new(packet[12], packet[13], packet[14], packet[15]);
+            let dst = Ipv4Addr::new(packet[16], packet[17], packet[18], packet[19]);
+            (packet[9], IpAddr::V4(src), IpAddr::V4(dst), packet.get(header..total.min(packet.len()))?)
+        },
+        6 => {
+            if packet.len() < 40 {
+                return None;
+            }
+            let length = u16::from_be_bytes([packet[4], packet[5]]) as usize;
+            let mut src = [0u8; 16];
+            src.copy_from_slice(packet.get(8..24)?);
+            let mut dst = [0u8; 16];
+            dst.copy_from_slice(packet.get(24..40)?);
+            (packet[6], IpAddr::V6(Ipv6Addr::from(src)), IpAddr::V6(Ipv6Addr::from(dst)), packet.get(40..(40 + length).min(packet.len()))?)
+        },
+        _ => return None
+    };
+    if transport.len() < 8 {
+        return None;
+    }
+    let src_port = u16::from_be_bytes([transport[0], transport[1]]);
+    let dst_port = u16::from_be_bytes([transport[2], transport[3]]);
+    let src = SocketAddr::new(src_ip, src_port);
+    let dst = SocketAddr::new(dst_ip, dst_port);
+    match protocol {
+        6 => {
+            if transport.len() < 20 {
+                return None;
+            }
+            let seq = u32::from_be_bytes([transport[4], transport[5], transport[6], transport[7]]);
+            let header = ((transport[12] >> 4) as usize) * 4;
+            if header < 20 {
+                return None;
+            }
+            let flags = transport[13];
+            Some(Segment{
+                time:frame.time,
+                protocol:StreamProtocol::Tcp,
+                src,
+                dst,
+                seq,
+                syn:flags & 0x02 != 0,
+                ack:flags & 0x10 != 0,
+                payload:transport.get(header..)?.to_vec(),
+            })
+        },
+        17 => {
+            let length = u16::from_be_bytes([transport[4], transport[5]]) as usize;
+            Some(Segment{
+                time:frame.time,
+                protocol:StreamProtocol::Udp,
+                src,
+                dst,
+                seq:0,
+                syn:false,
+                ack:false,
+                payload:transport.get(8..length.clamp(8, transport.len()))?.to_vec(),
+            })
+        },
+        _ => None
+    }
+}
+
+impl ReplayRunner {
+    pub fn new(recording:Recording, mode:ReplayMode, scale:f64) -> Self {
+        ReplayRunner{
+            messages:recording.messages,
+            mode,
+            scale,
+            index:0,
+            started:None,
+            waiting:None,
+            finished:false,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    pub fn progress(&self) -> String {
+        format!("回放{}/{}", self.index, self.messages.len())
+    }
+
+    // A response releases the next message in `WaitResponse` mode.
+    pub fn receive(&mut self) {
+        self.waiting = None;
+    }
+
+    fn due(&self, started:Instant) -> Instant {
+        let offset = self.messages[self.index].offset;
+        match self.mode {
+            ReplayMode::Original => started + offset,
+            ReplayMode::Scaled => started + offset.mul_f64(self.scale),
+            ReplayMode::WaitResponse => started
+        }
+    }
+
+    pub fn next_deadline(&self) -> Option<Instant> {
+        if self.finished || self.index >= self.messages.len() {
+            return None;
+        }
+        match (self.mode, self.started, self.waiting) {
+            (ReplayMode::WaitResponse, _, Some(deadline)) => Some(deadline),
+            (_, Some(started), _) => Some(self.due(started)),
+            _ => None
+        }
+    }
+
+    pub fn poll(&mut self, now:Instant) -> Vec<ReplayAction> {
+        let mut actions = Vec::new();
+        if self.finished {
+            return actions;
+        }
+        let started = *self.started.get_or_insert(now);
+        while self.index < self.messages.len() {
+            if self.mode == ReplayMode::WaitResponse {
+                match self.waiting {
+                    Some(deadline) if now < deadline => break,
+                    Some(_) => actions.push(ReplayAction::Log(format!("回放第{}条等待响应超时", self.index))),
+                    None => {}
+                }
+                self.waiting = Some(now + REPLAY_RESPONSE_TIMEOUT);
+            } else if now < self.due(started) {
+                break;
+            }
+            let payload = self.messages[self.index].payload.clone();
+            self.index += 1;
+            let display = format!("回放{}/{} [{}]", self.index, self.messages.len(), common::bytes_to_hex(&payload));
+            actions.push(ReplayAction::Send(payload, display));
+        }
+        if self.index >= self.messages.len() {
+            self.finished = true;
+            actions.push(ReplayAction::Log(format!("回放结束:{}条", self.messages.len())));
+            actions.push(ReplayAction::Finished);
+        }
+        actions
+    }
+
+    pub fn stop(&mut self) -> Vec<ReplayAction> {
+        if self.finished {
+            return Vec::new();
+        }
+        self.finished = true;
+        vec![ReplayAction::Log(format!("回放停止:{}", self.progress())), ReplayAction::Finished]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pcap::{Capture, CaptureEvent, CaptureProtocol};
+
+    fn ethernet_ipv4(ip:&[u8]) -> Frame {
+        let mut data = vec![0u8; 12];
+        data.extend_from_slice(&[0x08, 0x00]);
+        data.extend_from_slice(ip);
+        Frame{ time:Duration::ZERO, link_type:LINKTYPE_ETHERNET, data }
+    }
+
+    #[test]
+    fn truncated_frames_are_rejected() {
+        let mut ip = vec![0x45, 0, 0, 40, 0, 0, 0, 0, 64, 6, 0, 0, 127, 0, 0, 1, 127, 0, 0, 2];
+        assert!(decode_frame(&ethernet_ipv4(&ip[..15])).is_none());
+        ip[0] = 0x44;
+        assert!(decode_frame(&ethernet_ipv4(&ip)).is_none());
+        ip[0] = 0x45;
+        ip.extend_from_slice(&[0x1F, 0x90, 0x00]);
+        assert!(decode_frame(&ethernet_ipv4(&ip)).is_none());
+        let ipv6 = [0x60, 0, 0, 0, 0, 8, 17, 64, 0, 0];
+        assert!(decode_frame(&ethernet_ipv4(&ipv6)).is_none());
+    }
+
+    #[test]
+    fn capture_round_trip() {
+        let local : SocketAddr = "127.0.0.1:40000".parse().unwrap();
+        let remote : SocketAddr = "127.0.0.1:502".parse().unwrap();
+        let udp_remote : SocketAddr = "[::1]:7007".parse().unwrap();
+        let udp_local : SocketAddr = "[::1]:40001".parse().unwrap();
+        let mut capture = Capture::new();
+        capture.record(CaptureProtocol::Tcp, local, remote, CaptureEvent::Open(true));
+        capture.record(CaptureProtocol::Tcp, local, remote, CaptureEvent::Data(true, b"hello".to_vec()));
+        capture.record(CaptureProtocol::Tcp, local, remote, CaptureEvent::Data(false, b"reply".to_vec()));
+        capture.record(CaptureProtocol::Udp, udp_local, udp_remote, CaptureEvent::Data(true, b"ping".to_vec()));
+        capture.record(CaptureProtocol::Tcp, local, remote, CaptureEvent::Data(true, vec![7u8; 4000]));
+        capture.record(CaptureProtocol::Tcp, local, remote, CaptureEvent::Data(true, b"world".to_vec()));
+        capture.record(CaptureProtocol::Tcp, local, remote, CaptureEvent::Close);
+        let path = std::env::temp_dir().join(format!("rokit-replay-{}.pcapng", std::process::id()));
+        capture.write_pcapng(&path).unwrap();
+        let tcp = Recording::load(&path, 0);
+        let udp = Recording::load(&path, 1);
+        let missing = Recording::load(&path, 2);
+        let _ = std::fs::remove_file(&path);
+
+        let tcp = tcp.unwrap();
+        assert_eq!(tcp.protocol, Some(CaptureProtocol::Tcp));
+        let payload : Vec<u8> = tcp.messages.into_iter().flat_map(|m| m.payload).collect();
+        let mut expected = b"hello".to_vec();
+        expected.extend_from_slice(&[7u8; 4000]);
+        expected.extend_from_slice(b"world");
+        assert_eq!(payload, expected);
+        let udp = udp.unwrap();
+        assert_eq!(udp.protocol, Some(CaptureProtocol::Udp));
+        assert_eq!(udp.messages.len(), 1);
+        assert_eq!(udp.messages[0].payload, b"ping");
+        assert!(missing.is_err());
+    }
+}
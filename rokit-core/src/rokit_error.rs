@@ -0,0 +1,94 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::sync::Arc;
+
+#[derive(Debug, Clone)]
+pub enum RokitError {
+    AddrParse(String),
+    Connect(String, Arc<io::Error>),
+    Timeout(String, Option<Arc<io::Error>>),
+    Refused(String, Arc<io::Error>),
+    Reset(String, Arc<io::Error>),
+    PeerClosed(String),
+    Decode(String),
+    Io(String, Arc<io::Error>),
+}
+
+impl RokitError {
+
+    pub fn addr_parse(msg: String) -> Self {
+        RokitError::AddrParse(msg)
+    }
+
+    pub fn decode(msg: String) -> Self {
+        RokitError::Decode(msg)
+    }
+
+    pub fn peer_closed(msg: String) -> Self {
+        RokitError::PeerClosed(msg)
+    }
+
+    pub fn timeout(msg: String) -> Self {
+        RokitError::Timeout(msg, None)
+    }
+
+    pub fn connect(context: &str, e: io::Error) -> Self {
+        RokitError::classify(context, e, RokitError::Connect)
+    }
+
+    pub fn io(context: &str, e: io::Error) -> Self {
+        RokitError::classify(context, e, RokitError::Io)
+    }
+
+    fn classify(context: &str, e: io::Error, other: fn(String, Arc<io::Error>) -> RokitError) -> Self {
+        let context = context.to_string();
+        match e.kind() {
+            io::ErrorKind::ConnectionRefused => RokitError::Refused(context, Arc::new(e)),
+            io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::BrokenPipe => RokitError::Reset(context, Arc::new(e)),
+            io::ErrorKind::TimedOut => RokitError::Timeout(context, Some(Arc::new(e))),
+            io::ErrorKind::UnexpectedEof => RokitError::PeerClosed(context + ":" + e.to_string().as_str()),
+            _ => other(context, Arc::new(e)),
+        }
+    }
+
+    pub fn io_error(&self) -> Option<&io::Error> {
+        match self {
+            RokitError::Connect(_, e)
+            | RokitError::Refused(_, e)
+            | RokitError::Reset(_, e)
+            | RokitError::Io(_, e) => Some(e),
+            RokitError::Timeout(_, e) => e.as_deref(),
+            RokitError::AddrParse(_)
+            | RokitError::PeerClosed(_)
+            | RokitError::Decode(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for RokitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RokitError::AddrParse(msg)
+            | RokitError::PeerClosed(msg)
+            | RokitError::Decode(msg) => write!(f, "{}", msg),
+            RokitError::Timeout(context, None) => write!(f, "{}:超时", context),
+            RokitError::Connect(context, e)
+            | RokitError::Refused(context, e)
+            | RokitError::Reset(context, e)
+            | RokitError::Io(context, e)
+            | RokitError::Timeout(context, Some(e)) => write!(f, "{}:{}", context, e),
+        }
+    }
+}
+
+impl Error for RokitError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self.io_error() {
+            Some(e) => Some(e),
+            None => None
+        }
+    }
+}
@@ -0,0 +1,486 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use regex::bytes::Regex;
+
+use crate::common::{self, LineEnding};
+use crate::rokit_error::RokitError;
+use crate::text_encoding::TextEncoding;
+
+pub const SCRIPT_DEFAULT_TIMEOUT : Duration = Duration::from_secs(5);
+// Unmatched data kept while an expect waits: the newest bytes of the stream,
+// or the newest frames when framed.
+const SCRIPT_RECEIVE_MAX : usize = 64 * 1024;
+const SCRIPT_FRAMES_MAX : usize = 256;
+
+// 脚本格式, 每行一条, '#'开头为注释:
+//   send <文本>          发送文本, 支持转义和${变量}
+//   send-hex <HEX>       发送HEX, 支持${变量}
+//   expect <文本>        等待收到包含该文本的数据
+//   expect-hex <HEX>     等待收到包含该HEX的数据
+//   expect-re <正则>     等待收到匹配正则的数据, 命名分组(?P<name>..)保存为变量
+//   timeout <毫秒>       之后expect的等待时间, 默认5000
+//   sleep <毫秒>         等待
+//   loop [次数] ... end  重复, 不带次数时一直重复
+#[derive(Debug, Clone)]
+pub enum ScriptStep {
+    Send(String),
+    SendHex(String),
+    Expect(String),
+    ExpectHex(String),
+    ExpectRegex(Regex),
+    Timeout(Duration),
+    Sleep(Duration),
+    Loop(Option<u32>),
+    End,
+}
+
+#[derive(Debug, Clone)]
+pub struct ScriptLine {
+    pub line:usize,
+    pub text:String,
+    pub step:ScriptStep,
+}
+
+#[derive(Debug, Clone)]
+pub struct Script {
+    pub lines:Vec<ScriptLine>,
+}
+
+#[derive(Debug, Clone)]
+pub enum ScriptAction {
+    Send(Vec<u8>, String),
+    Log(String),
+    Finished(bool),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ScriptWait {
+    Expect(Instant),
+    Sleep(Instant),
+    Yield,
+}
+
+#[derive(Debug, Clone)]
+pub struct ScriptRunner {
+    script:Script,
+    encoding:TextEncoding,
+    pc:usize,
+    loops:Vec<(usize, Option<u32>)>,
+    variables:HashMap<String, String>,
+    framed:bool,
+    received:VecDeque<Vec<u8>>,
+    timeout:Duration,
+    wait:Option<ScriptWait>,
+    passed:usize,
+    finished:bool,
+}
+
+fn parse_millis(line:usize, value:&str) -> Result<Duration, RokitError> {
+    match value.trim().parse::<u64>() {
+        Ok(x) => Ok(Duration::from_millis(x)),
+        Err(_) => Err(RokitError::decode(format!("脚本第{}行错误, 毫秒格式错误:{}", line, value)))
+    }
+}
+
+impl Script {
+    pub fn parse(input:&str) -> Result<Script, RokitError> {
+        let mut lines = Vec::new();
+        let mut depth = 0;
+        for (index, raw) in input.lines().enumerate() {
+            let line = index + 1;
+            let text = raw.trim();
+            if text.is_empty() || text.starts_with('#') {
+                continue;
+            }
+            let (command, arg) = match text.split_once(char::is_whitespace) {
+                Some((c, a)) => (c, a.trim()),
+                None => (text, "")
+            };
+            let step = match command {
+                "send" => ScriptStep::Send(arg.to_string()),
+                "send-hex" => ScriptStep::SendHex(arg.to_string()),
+                "expect" => ScriptStep::Expect(arg.to_string()),
+                "expect-hex" => ScriptStep::ExpectHex(arg.to_string()),
+                "expect-re" => match Regex::new(arg) {
+                    Ok(x) => ScriptStep::ExpectRegex(x),
+                    Err(e) => return Err(RokitError::decode(format!("脚本第{}行错误, 正则格式错误:{}", line, e)))
+                },
+                "timeout" => ScriptStep::Timeout(parse_millis(line, arg)?),
+                "sleep" => ScriptStep::Sleep(parse_millis(line, arg)?),
+                "loop" => {
+                    depth += 1;
+                    if arg.is_empty() {
+                        ScriptStep::Loop(None)
+                    } else {
+                        match arg.parse::<u32>() {
+                            Ok(x) => ScriptStep::Loop(Some(x)),
+                            Err(_) => return Err(RokitError::decode(format!("脚本第{}行错误, 次数格式错误:{}", line, arg)))
+                        }
+                    }
+                },
+                "end" => {
+                    if depth == 0 {
+                        return Err(RokitError::decode(format!("脚本第{}行错误, 多余的end", line)));
+                    }
+                    depth -= 1;
+                    ScriptStep::End
+                },
+                _ => return Err(RokitError::decode(format!("脚本第{}行错误, 未知命令:{}", line, command)))
+            };
+            match step {
+                ScriptStep::SendHex(ref hex) | ScriptStep::ExpectHex(ref hex) if !hex.contains("${") => {
+                    if let Err(e) = common::parse_hex(hex.clone()) {
+                        return Err(RokitError::decode(format!("脚本第{}行错误, {}", line, e)));
+                    }
+                },
+                _ => {}
+            }
+            lines.push(ScriptLine{ line, text:text.to_string(), step });
+        }
+        if depth != 0 {
+            return Err(RokitError::decode("脚本错误, loop缺少end".to_string()));
+        }
+        Ok(Script{ lines })
+    }
+}
+
+impl ScriptRunner {
+    // With `framed` every expect is matched against a single received frame,
+    // otherwise against the byte stream.
+    pub fn new(script:Script, encoding:TextEncoding, framed:bool) -> Self {
+        ScriptRunner{
+            script,
+            encoding,
+            pc:0,
+            loops:Vec::new(),
+            variables:HashMap::new(),
+            framed,
+            received:VecDeque::new(),
+            timeout:SCRIPT_DEFAULT_TIMEOUT,
+            wait:None,
+            passed:0,
+            finished:false,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    pub fn variables(&self) -> &HashMap<String, String> {
+        &self.variables
+    }
+
+    pub fn receive(&mut self, buffer:&[u8]) {
+        if self.finished {
+            return;
+        }
+        match self.received.back_mut() {
+            Some(stream) if !self.framed => stream.extend_from_slice(buffer),
+            _ => self.received.push_back(buffer.to_vec())
+        }
+        if self.framed {
+            while self.received.len() > SCRIPT_FRAMES_MAX {
+                self.received.pop_front();
+            }
+        } else if let Some(stream) = self.received.back_mut() {
+            if stream.len() > SCRIPT_RECEIVE_MAX {
+                let excess = stream.len() - SCRIPT_RECEIVE_MAX;
+                stream.drain(..excess);
+            }
+        }
+    }
+
+    pub fn next_deadline(&self) -> Option<Instant> {
+        match self.wait {
+            Some(ScriptWait::Expect(x)) | Some(ScriptWait::Sleep(x)) => Some(x),
+            Some(ScriptWait::Yield) => Some(Instant::now()),
+            None if self.finished => None,
+            None => Some(Instant::now())
+        }
+    }
+
+    pub fn stop(&mut self) -> Vec<ScriptAction> {
+        if self.finished {
+            return Vec::new();
+        }
+        self.finished = true;
+        vec![ScriptAction::Log("脚本停止".to_string()), ScriptAction::Finished(false)]
+    }
+
+    fn substitute(&self, input:&str) -> String {
+        let mut output = String::new();
+        let mut rest = input;
+        while let Some(start) = rest.find("${") {
+            output.push_str(&rest[..start]);
+            match rest[start..].find('}') {
+                Some(end) => {
+                    let name = &rest[start + 2..start + end];
+                    match self.variables.get(name) {
+                        Some(value) => output.push_str(value),
+                        None => output.push_str(&rest[start..start + end + 1])
+                    }
+                    rest = &rest[start + end + 1..];
+                },
+                None => {
+                    output.push_str(&rest[start..]);
+                    rest = "";
+                }
+            }
+        }
+        output.push_str(rest);
+        output
+    }
+
+    fn matches(&mut self, step:&ScriptStep) -> Result<Option<String>, RokitError> {
+        while let Some(mut received) = self.received.pop_front() {
+            match self.match_frame(step, &received) {
+                Ok(Some((end, display))) => {
+                    if !self.framed && end < received.len() {
+                        received.drain(..end);
+                        self.received.push_front(received);
+                    }
+                    return Ok(Some(display));
+                },
+                Ok(None) if self.framed => {},
+                Ok(None) => {
+                    self.received.push_front(received);
+                    return Ok(None);
+                },
+                Err(e) => {
+                    self.received.push_front(received);
+                    return Err(e);
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    fn match_frame(&mut self, step:&ScriptStep, received:&[u8]) -> Result<Option<(usize, String)>, RokitError> {
+        let (end, display) = match step {
+            ScriptStep::Expect(text) => {
+                let pattern = common::unescape(&self.substitute(text), self.encoding)?;
+                match common::find_bytes(received, &pattern) {
+                    Some(index) => (index + pattern.len(), self.encoding.decode(&pattern)),
+                    None => return Ok(None)
+                }
+            },
+            ScriptStep::ExpectHex(hex) => {
+                let pattern = common::parse_hex(self.substitute(hex))?;
+                match common::find_bytes(received, &pattern) {
+                    Some(index) => (index + pattern.len(), common::bytes_to_hex(&pattern)),
+                    None => return Ok(None)
+                }
+            },
+            ScriptStep::ExpectRegex(regex) => {
+                let captures = match regex.captures(received) {
+                    Some(x) => x,
+                    None => return Ok(None)
+                };
+                let whole = match captures.get(0) {
+                    Some(x) => x,
+                    None => return Ok(None)
+                };
+                for name in regex.capture_names().flatten() {
+                    if let Some(value) = captures.name(name) {
+                        self.variables.insert(name.to_string(), self.encoding.decode(value.as_bytes()));
+                    }
+                }
+                (whole.end(), self.encoding.decode(whole.as_bytes()))
+            },
+            _ => return Ok(None)
+        };
+        Ok(Some((end, display)))
+    }
+
+    fn fail(&mut self, actions:&mut Vec<ScriptAction>, line:&ScriptLine, reason:String) {
+        self.finished = true;
+        self.wait = None;
+        actions.push(ScriptAction::Log(format!("脚本第{}行 失败:{} {}", line.line, line.text, reason)));
+        actions.push(ScriptAction::Log(format!("脚本结束: 通过{} 失败1", self.passed)));
+        actions.push(ScriptAction::Finished(false));
+    }
+
+    pub fn poll(&mut self, now:Instant) -> Vec<ScriptAction> {
+        let mut actions = Vec::new();
+        if self.finished {
+            return actions;
+        }
+        if let Some(ScriptWait::Yield) = self.wait {
+            self.wait = None;
+        }
+        loop {
+            let line = match self.script.lines.get(self.pc) {
+                Some(x) => x.clone(),
+                None => {
+                    self.finished = true;
+                    actions.push(ScriptAction::Log(format!("脚本结束: 通过{} 失败0", self.passed)));
+                    actions.push(ScriptAction::Finished(true));
+                    return actions;
+                }
+            };
+            match line.step {
+                ScriptStep::Send(ref text) => {
+                    let text = self.substitute(text);
+                    match common::text_to_bytes(&text, self.encoding, true, LineEnding::None) {
+                        Ok(buffer) => {
+                            let display = format!("{} [{}]", text, common::bytes_to_hex(&buffer));
+                            actions.push(ScriptAction::Send(buffer, display));
+                        },
+                        Err(e) => {
+                            self.fail(&mut actions, &line, e.to_string());
+                            return actions;
+                        }
+                    }
+                },
+                ScriptStep::SendHex(ref hex) => {
+                    match common::parse_hex(self.substitute(hex)) {
+                        Ok(buffer) => {
+                            let display = common::bytes_to_hex(&buffer);
+                            actions.push(ScriptAction::Send(buffer, display));
+                        },
+                        Err(e) => {
+                            self.fail(&mut actions, &line, e.to_string());
+                            return actions;
+                        }
+                    }
+                },
+                ScriptStep::Expect(_) | ScriptStep::ExpectHex(_) | ScriptStep::ExpectRegex(_) => {
+                    match self.matches(&line.step) {
+                        Ok(Some(display)) => {
+                            self.wait = None;
+                            self.passed += 1;
+                            actions.push(ScriptAction::Log(format!("脚本第{}行 通过:{} => {}", line.line, line.text, display)));
+                        },
+                        Ok(None) => {
+                            match self.wait {
+                                Some(ScriptWait::Expect(deadline)) if now >= deadline => {
+                                    self.fail(&mut actions, &line, RokitError::timeout("等待".to_string()).to_string());
+                                },
+                                Some(ScriptWait::Expect(_)) => {},
+                                _ => self.wait = Some(ScriptWait::Expect(now + self.timeout))
+                            }
+                            return actions;
+                        },
+                        Err(e) => {
+                            self.fail(&mut actions, &line, e.to_string());
+                            return actions;
+                        }
+                    }
+                },
+                ScriptStep::Timeout(timeout) => self.timeout = timeout,
+                ScriptStep::Sleep(duration) => {
+                    match self.wait {
+                        Some(ScriptWait::Sleep(until)) if now >= until => self.wait = None,
+                        Some(ScriptWait::Sleep(_)) => return actions,
+                        _ => {
+                            self.wait = Some(ScriptWait::Sleep(now + duration));
+                            return actions;
+                        }
+                    }
+                },
+                ScriptStep::Loop(count) => {
+                    if count == Some(0) {
+                        self.pc = self.matching_end(self.pc);
+                    } else {
+                        self.loops.push((self.pc, count));
+                    }
+                },
+                ScriptStep::End => {
+                    let repeat = match self.loops.last_mut() {
+                        Some((_, None)) => true,
+                        Some((_, Some(remaining))) => {
+                            *remaining -= 1;
+                            *remaining > 0
+                        },
+                        None => false
+                    };
+                    if repeat {
+                        if let Some((start, _)) = self.loops.last() {
+                            self.pc = *start + 1;
+                        }
+                        self.wait = Some(ScriptWait::Yield);
+                        return actions;
+                    }
+                    self.loops.pop();
+                }
+            }
+            self.pc += 1;
+        }
+    }
+
+    fn matching_end(&self, start:usize) -> usize {
+        let mut depth = 0;
+        for (index, line) in self.script.lines.iter().enumerate().skip(start) {
+            match line.step {
+                ScriptStep::Loop(_) => depth += 1,
+                ScriptStep::End => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return index;
+                    }
+                },
+                _ => {}
+            }
+        }
+        self.script.lines.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn passed(actions:&[ScriptAction]) -> bool {
+        actions.iter().any(|x| matches!(x, ScriptAction::Finished(true)))
+    }
+
+    #[test]
+    fn stream_expect_spans_reads() {
+        let script = Script::parse("expect hello world\nexpect-re id=(?P<id>\\d+)").unwrap();
+        let mut runner = ScriptRunner::new(script, TextEncoding::default(), false);
+        runner.receive(b"hello ");
+        runner.receive(b"world id=");
+        runner.receive(b"42;");
+        assert!(passed(&runner.poll(Instant::now())));
+        assert_eq!(runner.variables().get("id").map(String::as_str), Some("42"));
+    }
+
+    #[test]
+    fn framed_expect_matches_one_frame() {
+        let script = Script::parse("expect hello world\nexpect OK").unwrap();
+        let mut runner = ScriptRunner::new(script, TextEncoding::default(), true);
+        runner.receive(b"hello ");
+        runner.receive(b"world");
+        assert!(!passed(&runner.poll(Instant::now())));
+        runner.receive(b"ERR");
+        runner.receive(b">hello world<");
+        runner.receive(b"OK");
+        assert!(passed(&runner.poll(Instant::now())));
+    }
+
+    #[test]
+    fn unmatched_data_is_capped() {
+        let script = Script::parse("expect done").unwrap();
+        let mut runner = ScriptRunner::new(script.clone(), TextEncoding::default(), false);
+        let now = Instant::now();
+        runner.receive(b"do");
+        for _ in 0..10 {
+            runner.receive(&[b'x'; SCRIPT_RECEIVE_MAX / 4]);
+            assert!(!passed(&runner.poll(now)));
+        }
+        assert_eq!(runner.received.iter().map(Vec::len).sum::<usize>(), SCRIPT_RECEIVE_MAX);
+        runner.receive(b"do");
+        runner.receive(b"ne");
+        assert!(passed(&runner.poll(now)));
+
+        let mut runner = ScriptRunner::new(script, TextEncoding::default(), true);
+        for _ in 0..SCRIPT_FRAMES_MAX * 2 {
+            runner.receive(b"x");
+        }
+        assert_eq!(runner.received.len(), SCRIPT_FRAMES_MAX);
+        runner.receive(b"done");
+        assert!(passed(&runner.poll(now)));
+    }
+}
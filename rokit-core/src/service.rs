@@ -0,0 +1,261 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::io;
+use std::net::{self, SocketAddr};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use chrono::Local;
+use smol::channel::{bounded, Receiver, Sender};
+use smol::future;
+use smol::io::{AsyncReadExt, AsyncWriteExt};
+use smol::net::{TcpListener, TcpStream, UdpSocket};
+use smol::{Task, Timer};
+
+use crate::rokit_error::RokitError;
+
+const SERVICE_BUFFER_SIZE : usize = 65536;
+const CHARGEN_LINE_LEN : usize = 72;
+const CHARGEN_LINES : usize = 95;
+const CHARGEN_BLOCK_LINES : usize = 64;
+const CHARGEN_DATAGRAM_LEN : usize = 512;
+const SERVICE_ERROR_DELAY : Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ServiceKind {
+    #[default]
+    Echo,
+    Discard,
+    Chargen,
+    Daytime,
+}
+
+impl ServiceKind {
+    pub const ALL: [ServiceKind; 4] = [ServiceKind::Echo, ServiceKind::Discard, ServiceKind::Chargen, ServiceKind::Daytime];
+
+    // The standard ports 7, 9, 19 and 13 need privileges on most systems.
+    pub fn default_port(&self) -> u16 {
+        match self {
+            ServiceKind::Echo => 7007,
+            ServiceKind::Discard => 7009,
+            ServiceKind::Chargen => 7019,
+            ServiceKind::Daytime => 7013,
+        }
+    }
+}
+
+impl fmt::Display for ServiceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ServiceKind::Echo => "Echo回显",
+            ServiceKind::Discard => "Discard丢弃",
+            ServiceKind::Chargen => "Chargen字符流",
+            ServiceKind::Daytime => "Daytime时间",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ServiceProtocol {
+    #[default]
+    Tcp,
+    Udp,
+}
+
+impl ServiceProtocol {
+    pub const ALL: [ServiceProtocol; 2] = [ServiceProtocol::Tcp, ServiceProtocol::Udp];
+}
+
+impl fmt::Display for ServiceProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ServiceProtocol::Tcp => "TCP",
+            ServiceProtocol::Udp => "UDP",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// For UDP `connections` counts datagrams.
+#[derive(Debug, Default)]
+pub struct ServiceStats {
+    pub connections:AtomicU64,
+    pub received:AtomicU64,
+    pub sent:AtomicU64,
+}
+
+// RFC 864 pattern: 72 printable characters per line, each line starting one
+// character later than the previous one.
+pub fn chargen_line(index:usize) -> Vec<u8> {
+    let mut line : Vec<u8> = (0..CHARGEN_LINE_LEN).map(|i| b' ' + ((index + i) % CHARGEN_LINES) as u8).collect();
+    line.extend_from_slice(b"\r\n");
+    line
+}
+
+pub fn daytime() -> String {
+    Local::now().format("%A, %B %d, %Y %H:%M:%S %z\r\n").to_string()
+}
+
+// Dropping the service stops the listener and every connection it accepted.
+#[derive(Debug)]
+pub struct Service {
+    pub kind:ServiceKind,
+    pub protocol:ServiceProtocol,
+    pub local_addr:SocketAddr,
+    stats:Arc<ServiceStats>,
+    _stop:Sender<()>,
+    _task:Task<()>,
+}
+
+impl Service {
+    pub fn start(kind:ServiceKind, protocol:ServiceProtocol, addr:SocketAddr) -> Result<Service, RokitError> {
+        let stats = Arc::new(ServiceStats::default());
+        let (stop_tx, stop_rx) = bounded::<()>(1);
+        let (local_addr, task) = match protocol {
+            ServiceProtocol::Tcp => {
+                let listener = match net::TcpListener::bind(addr).and_then(TcpListener::try_from) {
+                    Ok(x) => x,
+                    Err(e) => return Err(RokitError::connect("TCP监听错误", e))
+                };
+                let local_addr = match listener.local_addr() {
+                    Ok(x) => x,
+                    Err(_) => addr
+                };
+                (local_addr, smol::spawn(accept_tcp(kind, listener, stats.clone(), stop_rx)))
+            },
+            ServiceProtocol::Udp => {
+                let socket = match net::UdpSocket::bind(addr).and_then(UdpSocket::try_from) {
+                    Ok(x) => x,
+                    Err(e) => return Err(RokitError::connect("UDP监听错误", e))
+                };
+                let local_addr = match socket.local_addr() {
+                    Ok(x) => x,
+                    Err(_) => addr
+                };
+                (local_addr, smol::spawn(serve_udp(kind, socket, stats.clone())))
+            }
+        };
+        Ok(Service{
+            kind,
+            protocol,
+            local_addr,
+            stats,
+            _stop:stop_tx,
+            _task:task,
+        })
+    }
+
+    pub fn describe(&self) -> String {
+        format!("{} {} {}", self.protocol, self.kind, self.local_addr)
+    }
+
+    // Returns (connections or datagrams, bytes received, bytes sent).
+    pub fn totals(&self) -> (u64, u64, u64) {
+        (self.stats.connections.load(Ordering::Relaxed),
+            self.stats.received.load(Ordering::Relaxed),
+            self.stats.sent.load(Ordering::Relaxed))
+    }
+}
+
+async fn accept_tcp(kind:ServiceKind, listener:TcpListener, stats:Arc<ServiceStats>, stop:Receiver<()>) {
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(_) => {
+                Timer::after(SERVICE_ERROR_DELAY).await;
+                continue;
+            }
+        };
+        stats.connections.fetch_add(1, Ordering::Relaxed);
+        let stats = stats.clone();
+        let stop = stop.clone();
+        smol::spawn(async move {
+            let serve = async {
+                let _ = serve_tcp(kind, stream, &stats).await;
+            };
+            let stopped = async {
+                let _ = stop.recv().await;
+            };
+            future::or(serve, stopped).await
+        }).detach();
+    }
+}
+
+async fn serve_tcp(kind:ServiceKind, mut stream:TcpStream, stats:&ServiceStats) -> io::Result<()> {
+    let mut buffer = vec![0u8; SERVICE_BUFFER_SIZE];
+    match kind {
+        ServiceKind::Echo => loop {
+            let n = stream.read(&mut buffer).await?;
+            if n == 0 {
+                return Ok(());
+            }
+            stats.received.fetch_add(n as u64, Ordering::Relaxed);
+            stream.write_all(&buffer[..n]).await?;
+            stats.sent.fetch_add(n as u64, Ordering::Relaxed);
+        },
+        ServiceKind::Discard => loop {
+            let n = stream.read(&mut buffer).await?;
+            if n == 0 {
+                return Ok(());
+            }
+            stats.received.fetch_add(n as u64, Ordering::Relaxed);
+        },
+        ServiceKind::Chargen => {
+            let block : Vec<u8> = (0..CHARGEN_LINES).flat_map(chargen_line).collect();
+            let block_len = (CHARGEN_LINE_LEN + 2) * CHARGEN_BLOCK_LINES;
+            let mut offset = 0;
+            loop {
+                let end = offset + block_len;
+                let chunk = if end <= block.len() {
+                    block[offset..end].to_vec()
+                } else {
+                    [&block[offset..], &block[..end - block.len()]].concat()
+                };
+                stream.write_all(&chunk).await?;
+                stats.sent.fetch_add(chunk.len() as u64, Ordering::Relaxed);
+                offset = end % block.len();
+            }
+        },
+        ServiceKind::Daytime => {
+            let time = daytime();
+            stream.write_all(time.as_bytes()).await?;
+            stats.sent.fetch_add(time.len() as u64, Ordering::Relaxed);
+            stream.close().await
+        }
+    }
+}
+
+async fn serve_udp(kind:ServiceKind, socket:UdpSocket, stats:Arc<ServiceStats>) {
+    let mut buffer = vec![0u8; SERVICE_BUFFER_SIZE];
+    let mut line = 0;
+    loop {
+        let (n, addr) = match socket.recv_from(&mut buffer).await {
+            Ok(x) => x,
+            Err(_) => {
+                Timer::after(SERVICE_ERROR_DELAY).await;
+                continue;
+            }
+        };
+        stats.connections.fetch_add(1, Ordering::Relaxed);
+        stats.received.fetch_add(n as u64, Ordering::Relaxed);
+        let reply = match kind {
+            ServiceKind::Echo => buffer[..n].to_vec(),
+            ServiceKind::Discard => continue,
+            ServiceKind::Chargen => {
+                let mut reply = Vec::with_capacity(CHARGEN_DATAGRAM_LEN);
+                while reply.len() < CHARGEN_DATAGRAM_LEN {
+                    reply.extend(chargen_line(line));
+                    line = (line + 1) % CHARGEN_LINES;
+                }
+                reply.truncate(CHARGEN_DATAGRAM_LEN);
+                reply
+            },
+            ServiceKind::Daytime => daytime().into_bytes()
+        };
+        if let Ok(sent) = socket.send_to(&reply, addr).await {
+            stats.sent.fetch_add(sent as u64, Ordering::Relaxed);
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use crate::rokit_error::RokitError;
+use crate::common::DEFAULT_BUFFER_SIZE;
+use crate::transport::{BoxFuture, Transport, TransportConfig, TransportEvent};
+use std::net::{SocketAddr, Shutdown};
+use std::time::Duration;
+use smol::future;
+use smol::io::{AsyncReadExt, AsyncWriteExt};
+use smol::net::TcpStream;
+use smol::Timer;
+
+// A peer that stops reading must not stall the transport forever.
+pub const TCP_WRITE_TIMEOUT : Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone)]
+pub struct TcpClient {
+    pub socket_addr:SocketAddr,
+    pub local_addr:SocketAddr,
+    pub tcp_stream:TcpStream,
+    pub buffer_size:usize
+}
+
+impl TcpClient {
+    pub async fn connect(socket_addr:SocketAddr) -> Result<Self, RokitError> {
+        let tcp = TcpStream::connect(socket_addr).await;
+        match tcp {
+            Ok(t) => TcpClient::from_stream(t, socket_addr),
+            Err(e) => {
+                Err(RokitError::connect("TCP连接错误", e))
+            }
+        }
+    }
+
+    pub fn from_stream(tcp_stream:TcpStream, socket_addr:SocketAddr) -> Result<Self, RokitError> {
+        match tcp_stream.local_addr() {
+            Ok(local_addr) => Ok(TcpClient{
+                socket_addr,
+                local_addr,
+                tcp_stream,
+                buffer_size:DEFAULT_BUFFER_SIZE
+            }),
+            Err(e) => Err(RokitError::io("TCP连接错误", e))
+        }
+    }
+
+    pub async fn send(&mut self, buffer:&[u8]) -> Result<u32, RokitError>{
+        let tcp_stream = &mut self.tcp_stream;
+        let written = future::or(
+            async { Some(tcp_stream.write_all(buffer).await) },
+            async {
+                Timer::after(TCP_WRITE_TIMEOUT).await;
+                None
+            }
+        ).await;
+        match written {
+            Some(Ok(_)) => Ok(buffer.len() as u32),
+            Some(Err(e)) => Err(RokitError::io("TCP写入错误", e)),
+            None => Err(RokitError::timeout(format!("TCP写入{} {}", self.socket_addr.ip(), self.socket_addr.port())))
+        }
+    }
+
+    pub fn set_buffer_size(&mut self, buffer_size:usize) {
+        self.buffer_size = buffer_size;
+    }
+
+    pub async fn read(&mut self) -> Result<Vec<u8>, RokitError>{
+        let mut buffer = vec![0u8; self.buffer_size];
+        match self.tcp_stream.read(&mut buffer).await {
+            Ok(x) => {
+                if x == 0 {
+                    return  Err(RokitError::peer_closed(format!("TCP断开:{} {}", self.socket_addr.ip(), self.socket_addr.port())));
+                }
+                buffer.truncate(x);
+                Ok(buffer)
+            },
+            Err(e) => Err(RokitError::io("TCP读取错误", e))
+        }
+    }
+
+    pub fn disconnect(&mut self) -> Result<(), RokitError>{
+        match self.tcp_stream.shutdown(Shutdown::Both) {
+            Ok(x) => Ok(x),
+            Err(e) => Err(RokitError::io("TCP断开错误", e))
+        }
+    }
+}
+
+impl Transport for TcpClient {
+    fn connect(config:TransportConfig) -> BoxFuture<'static, Result<Self, RokitError>> {
+        Box::pin(async move {
+            let mut client = TcpClient::connect(config.socket_addr).await?;
+            client.set_buffer_size(config.buffer_size);
+            Ok(client)
+        })
+    }
+
+    fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    fn remote_addr(&self) -> SocketAddr {
+        self.socket_addr
+    }
+
+    fn send<'a>(&'a mut self, _target:Option<SocketAddr>, buffer:&'a [u8]) -> BoxFuture<'a, Result<Vec<TransportEvent>, RokitError>> {
+        Box::pin(async move {
+            TcpClient::send(self, buffer).await?;
+            Ok(Vec::new())
+        })
+    }
+
+    fn next_event(&mut self) -> BoxFuture<'_, Result<TransportEvent, RokitError>> {
+        Box::pin(async move {
+            let buffer = self.read().await?;
+            Ok(TransportEvent::Received(self.socket_addr, buffer))
+        })
+    }
+
+    fn close(&mut self) {
+        let _ = self.disconnect();
+    }
+}
@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use smol::channel::{unbounded, Receiver, Sender};
+use smol::net::TcpListener;
+use smol::Task;
+
+use crate::rokit_error::RokitError;
+use crate::tcp_client::TcpClient;
+use crate::common::DEFAULT_BUFFER_SIZE;
+use crate::transport::{BoxFuture, Transport, TransportConfig, TransportEvent};
+
+#[derive(Debug)]
+pub struct TcpServer {
+    pub socket_addr:SocketAddr,
+    pub tcp_listener:TcpListener,
+    pub buffer_size:usize,
+    peers:HashMap<SocketAddr, (TcpClient, Task<()>)>,
+    acceptor:Option<Task<()>>,
+    incoming:(Sender<Incoming>, Receiver<Incoming>),
+}
+
+#[derive(Debug)]
+enum Incoming {
+    Accepted(TcpClient),
+    Received(SocketAddr, Vec<u8>),
+    Closed(SocketAddr, RokitError),
+    ListenerClosed(RokitError),
+}
+
+impl TcpServer {
+    pub async fn bind(res:SocketAddr) -> Result<Self, RokitError> {
+        match TcpListener::bind(res).await {
+            Ok(listener) => {
+                let socket_addr = match listener.local_addr() {
+                    Ok(x) => x,
+                    Err(_) => res
+                };
+                Ok(TcpServer{
+                    socket_addr,
+                    tcp_listener:listener,
+                    buffer_size:DEFAULT_BUFFER_SIZE,
+                    peers:HashMap::new(),
+                    acceptor:None,
+                    incoming:unbounded(),
+                })
+            },
+            Err(e) => Err(RokitError::connect("TCP监听错误", e))
+        }
+    }
+
+    pub async fn accept(&mut self) -> Result<TcpClient, RokitError> {
+        TcpServer::accept_from(&self.tcp_listener, self.buffer_size).await
+    }
+
+    async fn accept_from(listener:&TcpListener, buffer_size:usize) -> Result<TcpClient, RokitError> {
+        match listener.accept().await {
+            Ok((stream, addr)) => {
+                let mut peer = TcpClient::from_stream(stream, addr)?;
+                peer.set_buffer_size(buffer_size);
+                Ok(peer)
+            },
+            Err(e) => Err(RokitError::io("TCP监听错误", e))
+        }
+    }
+
+    pub fn set_buffer_size(&mut self, buffer_size:usize) {
+        self.buffer_size = buffer_size;
+    }
+
+    pub fn peers(&self) -> Vec<SocketAddr> {
+        self.peers.keys().cloned().collect()
+    }
+
+    fn start_acceptor(&mut self) {
+        let listener = self.tcp_listener.clone();
+        let buffer_size = self.buffer_size;
+        let sender = self.incoming.0.clone();
+        self.acceptor = Some(smol::spawn(async move {
+            loop {
+                let message = match TcpServer::accept_from(&listener, buffer_size).await {
+                    Ok(peer) => Incoming::Accepted(peer),
+                    Err(e) => {
+                        let _ = sender.send(Incoming::ListenerClosed(e)).await;
+                        break;
+                    }
+                };
+                if sender.send(message).await.is_err() {
+                    break;
+                }
+            }
+        }));
+    }
+
+    fn start_reader(&self, mut peer:TcpClient) -> Task<()> {
+        let sender = self.incoming.0.clone();
+        smol::spawn(async move {
+            loop {
+                let message = match peer.read().await {
+                    Ok(buffer) => Incoming::Received(peer.socket_addr, buffer),
+                    Err(e) => {
+                        let _ = sender.send(Incoming::Closed(peer.socket_addr, e)).await;
+                        break;
+                    }
+                };
+                if sender.send(message).await.is_err() {
+                    break;
+                }
+            }
+        })
+    }
+
+    fn remove_peer(&mut self, addr:SocketAddr) -> bool {
+        match self.peers.remove(&addr) {
+            Some((mut peer, _)) => {
+                let _ = peer.disconnect();
+                true
+            },
+            None => false
+        }
+    }
+}
+
+impl Transport for TcpServer {
+    fn connect(config:TransportConfig) -> BoxFuture<'static, Result<Self, RokitError>> {
+        Box::pin(async move {
+            let mut server = TcpServer::bind(config.socket_addr).await?;
+            server.set_buffer_size(config.buffer_size);
+            Ok(server)
+        })
+    }
+
+    fn local_addr(&self) -> SocketAddr {
+        self.socket_addr
+    }
+
+    fn remote_addr(&self) -> SocketAddr {
+        self.socket_addr
+    }
+
+    fn send<'a>(&'a mut self, target:Option<SocketAddr>, buffer:&'a [u8]) -> BoxFuture<'a, Result<Vec<TransportEvent>, RokitError>> {
+        Box::pin(async move {
+            let targets = match target {
+                Some(addr) => vec![addr],
+                None => self.peers()
+            };
+            // Each peer is written from its own task so a stalled peer only
+            // delays the others by the write timeout, then it is dropped.
+            let buffer : Arc<[u8]> = Arc::from(buffer);
+            let mut writes = Vec::new();
+            for addr in targets {
+                let mut peer = match self.peers.get(&addr) {
+                    Some((peer, _)) => peer.clone(),
+                    None => continue
+                };
+                let buffer = buffer.clone();
+                writes.push(smol::spawn(async move {
+                    let res = peer.send(&buffer).await;
+                    (addr, res)
+                }));
+            }
+            let mut reported = Vec::new();
+            for write in writes {
+                if let (addr, Err(e)) = write.await {
+                    if self.remove_peer(addr) {
+                        reported.push(TransportEvent::PeerDisconnected(addr, e));
+                    }
+                }
+            }
+            Ok(reported)
+        })
+    }
+
+    fn next_event(&mut self) -> BoxFuture<'_, Result<TransportEvent, RokitError>> {
+        Box::pin(async move {
+            if self.acceptor.is_none() {
+                self.start_acceptor();
+            }
+            loop {
+                let message = match self.incoming.1.recv().await {
+                    Ok(x) => x,
+                    Err(_) => return Err(RokitError::peer_closed("TCP停止监听".to_string()))
+                };
+                match message {
+                    Incoming::Accepted(peer) => {
+                        let addr = peer.socket_addr;
+                        let local_addr = peer.local_addr;
+                        let reader = self.start_reader(peer.clone());
+                        self.peers.insert(addr, (peer, reader));
+                        return Ok(TransportEvent::PeerConnected(addr, local_addr));
+                    },
+                    Incoming::Received(addr, buffer) => {
+                        if self.peers.contains_key(&addr) {
+                            return Ok(TransportEvent::Received(addr, buffer));
+                        }
+                    },
+                    Incoming::Closed(addr, e) => {
+                        if self.remove_peer(addr) {
+                            return Ok(TransportEvent::PeerDisconnected(addr, e));
+                        }
+                    },
+                    Incoming::ListenerClosed(e) => return Err(e)
+                }
+            }
+        })
+    }
+
+    fn close(&mut self) {
+        self.acceptor = None;
+        for addr in self.peers() {
+            self.remove_peer(addr);
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use std::fmt;
+
+use encoding_rs::{Encoding, EncoderResult, BIG5, GB18030, GBK, UTF_16BE, UTF_16LE, UTF_8};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TextEncoding {
+    #[default]
+    Utf8,
+    Gbk,
+    Gb18030,
+    Big5,
+    Utf16Le,
+    Utf16Be,
+    Latin1,
+}
+
+impl TextEncoding {
+    pub const ALL: [TextEncoding; 7] = [
+        TextEncoding::Utf8,
+        TextEncoding::Gbk,
+        TextEncoding::Gb18030,
+        TextEncoding::Big5,
+        TextEncoding::Utf16Le,
+        TextEncoding::Utf16Be,
+        TextEncoding::Latin1,
+    ];
+
+    pub fn from_name(name:&str) -> Option<TextEncoding> {
+        let name = name.replace(['-', '_'], "");
+        TextEncoding::ALL.iter().cloned().find(|e| e.to_string().replace('-', "").eq_ignore_ascii_case(&name))
+    }
+
+    pub fn encode(&self, s:&str) -> Vec<u8> {
+        match self {
+            TextEncoding::Utf8 => s.as_bytes().to_vec(),
+            TextEncoding::Utf16Le => s.encode_utf16().flat_map(|u| u.to_le_bytes()).collect(),
+            TextEncoding::Utf16Be => s.encode_utf16().flat_map(|u| u.to_be_bytes()).collect(),
+            TextEncoding::Latin1 => s.chars().map(|c| if (c as u32) <= 0xFF { c as u8 } else { b'?' }).collect(),
+            TextEncoding::Gbk => encode_with(GBK, s),
+            TextEncoding::Gb18030 => encode_with(GB18030, s),
+            TextEncoding::Big5 => encode_with(BIG5, s),
+        }
+    }
+
+    pub fn decode(&self, buffer:&[u8]) -> String {
+        match self {
+            TextEncoding::Latin1 => buffer.iter().map(|b| *b as char).collect(),
+            _ => self.encoding().decode_without_bom_handling(buffer).0.into_owned()
+        }
+    }
+
+    fn encoding(&self) -> &'static Encoding {
+        match self {
+            TextEncoding::Utf8 | TextEncoding::Latin1 => UTF_8,
+            TextEncoding::Gbk => GBK,
+            TextEncoding::Gb18030 => GB18030,
+            TextEncoding::Big5 => BIG5,
+            TextEncoding::Utf16Le => UTF_16LE,
+            TextEncoding::Utf16Be => UTF_16BE,
+        }
+    }
+}
+
+impl fmt::Display for TextEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            TextEncoding::Utf8 => "UTF-8",
+            TextEncoding::Gbk => "GBK",
+            TextEncoding::Gb18030 => "GB18030",
+            TextEncoding::Big5 => "Big5",
+            TextEncoding::Utf16Le => "UTF-16LE",
+            TextEncoding::Utf16Be => "UTF-16BE",
+            TextEncoding::Latin1 => "ISO-8859-1",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+fn encode_with(encoding:&'static Encoding, s:&str) -> Vec<u8> {
+    let mut encoder = encoding.new_encoder();
+    let mut buffer : Vec<u8> = Vec::with_capacity(s.len() * 2 + 16);
+    let mut input = s;
+    loop {
+        let (result, read) = encoder.encode_from_utf8_to_vec_without_replacement(input, &mut buffer, true);
+        input = &input[read..];
+        match result {
+            EncoderResult::InputEmpty => break,
+            EncoderResult::OutputFull => buffer.reserve(input.len() * 2 + 16),
+            EncoderResult::Unmappable(_) => buffer.push(b'?'),
+        }
+    }
+    buffer
+}
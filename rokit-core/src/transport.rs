@@ -0,0 +1,168 @@
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+
+use serde::{Deserialize, Serialize};
+use smol::channel::{unbounded, Sender};
+use smol::future;
+use smol::stream::{self, Stream};
+
+use crate::rokit_error::RokitError;
+use crate::tcp_client::TcpClient;
+use crate::tcp_server::TcpServer;
+use crate::udp_client::UdpClient;
+use crate::udp_server::UdpServer;
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+pub type BoxStream<T> = Pin<Box<dyn Stream<Item = T> + Send>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Protocol {
+    TcpClient,
+    TcpServer,
+    UdpClient,
+    UdpServer,
+}
+
+impl Protocol {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Protocol::TcpClient | Protocol::TcpServer => "TCP",
+            Protocol::UdpClient | Protocol::UdpServer => "UDP",
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Protocol::TcpClient => "TCP客户端",
+            Protocol::TcpServer => "TCP服务端",
+            Protocol::UdpClient => "UDP客户端",
+            Protocol::UdpServer => "UDP服务端",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TransportConfig {
+    pub protocol:Protocol,
+    pub socket_addr:SocketAddr,
+    pub local_ip:String,
+    pub local_port:String,
+    pub buffer_size:usize,
+}
+
+#[derive(Debug, Clone)]
+pub enum TransportCommand {
+    Send(Option<SocketAddr>, Vec<u8>),
+    Close,
+}
+
+#[derive(Debug, Clone)]
+pub struct TransportHandle {
+    sender:Sender<TransportCommand>
+}
+
+impl TransportHandle {
+    pub fn send(&self, command:TransportCommand) -> Result<(), RokitError> {
+        match self.sender.try_send(command) {
+            Ok(_) => Ok(()),
+            Err(_) => Err(RokitError::peer_closed("连接已关闭".to_string()))
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum TransportEvent {
+    Connected(TransportHandle, SocketAddr, SocketAddr),
+    // Peer and the local address of the accepted connection.
+    PeerConnected(SocketAddr, SocketAddr),
+    PeerDisconnected(SocketAddr, RokitError),
+    Received(SocketAddr, Vec<u8>),
+    Error(RokitError),
+}
+
+// An open socket (or listener) that can be driven by `open`.
+// `next_event` must be cancel safe, it is raced against incoming commands.
+// Errors returned from `next_event` or `send` close the transport, recoverable
+// failures are reported as events instead.
+pub trait Transport: Send + Sized + 'static {
+    fn connect(config:TransportConfig) -> BoxFuture<'static, Result<Self, RokitError>>;
+
+    fn local_addr(&self) -> SocketAddr;
+
+    fn remote_addr(&self) -> SocketAddr;
+
+    fn send<'a>(&'a mut self, target:Option<SocketAddr>, buffer:&'a [u8]) -> BoxFuture<'a, Result<Vec<TransportEvent>, RokitError>>;
+
+    fn next_event(&mut self) -> BoxFuture<'_, Result<TransportEvent, RokitError>>;
+
+    fn close(&mut self);
+}
+
+// Connects according to `config` on a background task. The stream starts with
+// `Connected` (or a single `Error`) and ends when the transport closes; dropping
+// it closes the transport.
+pub fn open(config:TransportConfig) -> BoxStream<TransportEvent> {
+    match config.protocol {
+        Protocol::TcpClient => open_with::<TcpClient>(config),
+        Protocol::TcpServer => open_with::<TcpServer>(config),
+        Protocol::UdpClient => open_with::<UdpClient>(config),
+        Protocol::UdpServer => open_with::<UdpServer>(config),
+    }
+}
+
+pub fn open_with<T: Transport>(config:TransportConfig) -> BoxStream<TransportEvent> {
+    let (event_tx, event_rx) = unbounded();
+    let worker = smol::spawn(run::<T>(config, event_tx));
+    Box::pin(stream::unfold((event_rx, worker), |(event_rx, worker)| async move {
+        match event_rx.recv().await {
+            Ok(event) => Some((event, (event_rx, worker))),
+            Err(_) => None
+        }
+    }))
+}
+
+enum Next {
+    Command(Option<TransportCommand>),
+    Event(Result<TransportEvent, RokitError>),
+}
+
+async fn run<T: Transport>(config:TransportConfig, events:Sender<TransportEvent>) {
+    if let Err(e) = serve::<T>(config, &events).await {
+        let _ = events.send(TransportEvent::Error(e)).await;
+    }
+}
+
+async fn serve<T: Transport>(config:TransportConfig, events:&Sender<TransportEvent>) -> Result<(), RokitError> {
+    let mut transport = T::connect(config).await?;
+    let (command_tx, command_rx) = unbounded();
+    let handle = TransportHandle { sender: command_tx };
+    let _ = events.send(TransportEvent::Connected(handle, transport.local_addr(), transport.remote_addr())).await;
+    let result = loop {
+        let next = future::or(
+            async { Next::Command(command_rx.recv().await.ok()) },
+            async { Next::Event(transport.next_event().await) }
+        ).await;
+        match next {
+            Next::Command(Some(TransportCommand::Send(target, buffer))) => {
+                match transport.send(target, &buffer).await {
+                    Ok(reported) => {
+                        for event in reported {
+                            let _ = events.send(event).await;
+                        }
+                    },
+                    Err(e) => break Err(e)
+                }
+            },
+            Next::Command(Some(TransportCommand::Close)) | Next::Command(None) => break Ok(()),
+            Next::Event(Ok(event)) => {
+                if events.send(event).await.is_err() {
+                    break Ok(());
+                }
+            },
+            Next::Event(Err(e)) => break Err(e)
+        }
+    };
+    transport.close();
+    result
+}
@@ -0,0 +1,120 @@
+use std::net::{Ipv6Addr, SocketAddr};
+
+use smol::net::UdpSocket;
+
+use crate::rokit_error::RokitError;
+use crate::common::{parse_ip_port, DEFAULT_BUFFER_SIZE};
+use crate::transport::{BoxFuture, Transport, TransportConfig, TransportEvent};
+
+pub const UDP_LOCAL_IP_ANY : &str = "0.0.0.0";
+pub const UDP_LOCAL_PORT_ANY : &str = "0";
+
+#[derive(Debug, Clone)]
+pub struct UdpClient {
+    pub socket_addr:SocketAddr,
+    pub local_addr:SocketAddr,
+    pub udp_stream:UdpSocket,
+    pub buffer_size:usize
+}
+
+#[derive(Debug, Clone)]
+pub struct UdpClientReceive {
+    pub socket_addr:SocketAddr,
+    pub result:Vec<u8>,
+}
+
+impl UdpClient {
+    pub async fn connect(res:SocketAddr, local_ip:String, local_port:String) -> Result<Self, RokitError> {
+        let local_ip = if local_ip.trim().is_empty() { String::from(UDP_LOCAL_IP_ANY) } else { local_ip };
+        let local_port = if local_port.trim().is_empty() { String::from(UDP_LOCAL_PORT_ANY) } else { local_port };
+        let mut local = parse_ip_port(local_ip, local_port)?;
+        if res.is_ipv6() && local.ip().is_unspecified() {
+            local.set_ip(Ipv6Addr::UNSPECIFIED.into());
+        }
+        match UdpSocket::bind(local).await {
+            Ok(udp) => {
+                match udp.connect(res).await {
+                    Ok(_) => {},
+                    Err(e) => {
+                        return Err(RokitError::connect("UDP连接错误", e))
+                    }
+                }
+                let local_addr = match udp.local_addr() {
+                    Ok(x) => x,
+                    Err(_) => local
+                };
+                Ok(UdpClient{
+                    socket_addr:res,
+                    local_addr,
+                    udp_stream:udp,
+                    buffer_size:DEFAULT_BUFFER_SIZE
+                })
+            },
+            Err(e) => {
+                Err(RokitError::connect(format!("UDP绑定错误:{}", local).as_str(), e))
+            }
+        }
+    }
+
+    pub async fn send(&mut self, buffer:&[u8]) -> Result<u32, RokitError>{
+        match self.udp_stream.send(buffer).await {
+            Ok(x) => Ok(x as u32),
+            Err(e) => Err(RokitError::io("UDP写入错误", e))
+        }
+    }
+
+    pub fn set_buffer_size(&mut self, buffer_size:usize) {
+        self.buffer_size = buffer_size;
+    }
+
+    pub async fn read(&mut self) -> Result<UdpClientReceive, RokitError>{
+        let mut buffer = vec![0u8; self.buffer_size];
+        match self.udp_stream.recv_from(&mut buffer).await {
+            Ok((i, addr)) => {
+                buffer.truncate(i);
+                Ok(UdpClientReceive{socket_addr:addr, result:buffer})
+            },
+            Err(e) => Err(RokitError::io("UDP读取错误", e))
+        }
+    }
+}
+
+impl Transport for UdpClient {
+    fn connect(config:TransportConfig) -> BoxFuture<'static, Result<Self, RokitError>> {
+        Box::pin(async move {
+            let mut client = UdpClient::connect(config.socket_addr, config.local_ip, config.local_port).await?;
+            client.set_buffer_size(config.buffer_size);
+            Ok(client)
+        })
+    }
+
+    fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    fn remote_addr(&self) -> SocketAddr {
+        self.socket_addr
+    }
+
+    fn send<'a>(&'a mut self, _target:Option<SocketAddr>, buffer:&'a [u8]) -> BoxFuture<'a, Result<Vec<TransportEvent>, RokitError>> {
+        Box::pin(async move {
+            match UdpClient::send(self, buffer).await {
+                Ok(_) => Ok(Vec::new()),
+                Err(e @ RokitError::Refused(..)) | Err(e @ RokitError::Reset(..)) => Ok(vec![TransportEvent::Error(e)]),
+                Err(e) => Err(e)
+            }
+        })
+    }
+
+    fn next_event(&mut self) -> BoxFuture<'_, Result<TransportEvent, RokitError>> {
+        Box::pin(async move {
+            match self.read().await {
+                Ok(r) => Ok(TransportEvent::Received(r.socket_addr, r.result)),
+                Err(e @ RokitError::Refused(..)) | Err(e @ RokitError::Reset(..)) => Ok(TransportEvent::Error(e)),
+                Err(e) => Err(e)
+            }
+        })
+    }
+
+    fn close(&mut self) {}
+}
@@ -0,0 +1,102 @@
+use std::io;
+use std::net::SocketAddr;
+
+use smol::net::UdpSocket;
+
+use crate::rokit_error::RokitError;
+use crate::common::DEFAULT_BUFFER_SIZE;
+use crate::transport::{BoxFuture, Transport, TransportConfig, TransportEvent};
+use crate::udp_client::UdpClientReceive;
+
+#[derive(Debug, Clone)]
+pub struct UdpServer {
+    pub socket_addr:SocketAddr,
+    pub udp_socket:UdpSocket,
+    pub buffer_size:usize
+}
+
+impl UdpServer {
+    pub async fn bind(res:SocketAddr) -> Result<Self, RokitError> {
+        match UdpSocket::bind(res).await {
+            Ok(udp) => {
+                let socket_addr = match udp.local_addr() {
+                    Ok(x) => x,
+                    Err(_) => res
+                };
+                Ok(UdpServer{
+                    socket_addr,
+                    udp_socket:udp,
+                    buffer_size:DEFAULT_BUFFER_SIZE
+                })
+            },
+            Err(e) => Err(RokitError::connect("UDP监听错误", e))
+        }
+    }
+
+    pub async fn send_to(&mut self, buffer:&[u8], addr:SocketAddr) -> Result<u32, RokitError>{
+        match self.udp_socket.send_to(buffer, addr).await {
+            Ok(x) => Ok(x as u32),
+            Err(e) => Err(RokitError::io("UDP写入错误", e))
+        }
+    }
+
+    pub fn set_buffer_size(&mut self, buffer_size:usize) {
+        self.buffer_size = buffer_size;
+    }
+
+    pub async fn read(&mut self) -> Result<UdpClientReceive, RokitError>{
+        let mut buffer = vec![0u8; self.buffer_size];
+        loop {
+            match self.udp_socket.recv_from(&mut buffer).await {
+                Ok((i, addr)) => {
+                    buffer.truncate(i);
+                    return Ok(UdpClientReceive{socket_addr:addr, result:buffer})
+                },
+                Err(ref e) if e.kind() == io::ErrorKind::ConnectionReset => {
+                    continue;
+                }
+                Err(e) => return Err(RokitError::io("UDP读取错误", e))
+            }
+        }
+    }
+}
+
+impl Transport for UdpServer {
+    fn connect(config:TransportConfig) -> BoxFuture<'static, Result<Self, RokitError>> {
+        Box::pin(async move {
+            let mut server = UdpServer::bind(config.socket_addr).await?;
+            server.set_buffer_size(config.buffer_size);
+            Ok(server)
+        })
+    }
+
+    fn local_addr(&self) -> SocketAddr {
+        self.socket_addr
+    }
+
+    fn remote_addr(&self) -> SocketAddr {
+        self.socket_addr
+    }
+
+    fn send<'a>(&'a mut self, target:Option<SocketAddr>, buffer:&'a [u8]) -> BoxFuture<'a, Result<Vec<TransportEvent>, RokitError>> {
+        Box::pin(async move {
+            let addr = match target {
+                Some(x) => x,
+                None => return Ok(vec![TransportEvent::Error(RokitError::peer_closed("UDP无客户端, 数据未发送".to_string()))])
+            };
+            match self.send_to(buffer, addr).await {
+                Ok(_) => Ok(Vec::new()),
+                Err(e) => Ok(vec![TransportEvent::PeerDisconnected(addr, e)])
+            }
+        })
+    }
+
+    fn next_event(&mut self) -> BoxFuture<'_, Result<TransportEvent, RokitError>> {
+        Box::pin(async move {
+            let r = self.read().await?;
+            Ok(TransportEvent::Received(r.socket_addr, r.result))
+        })
+    }
+
+    fn close(&mut self) {}
+}
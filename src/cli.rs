@@ -0,0 +1,598 @@
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Write};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use rokit_core::checksum::Checksum;
+use rokit_core::common::{self, LineEnding};
+use rokit_core::framing::{Framer, Framing, FramingMode};
+use rokit_core::log::generate_log;
+use rokit_core::replay::{Recording, ReplayAction, ReplayMode, ReplayRunner};
+use rokit_core::rokit_error::RokitError;
+use rokit_core::script::{Script, ScriptAction, ScriptRunner};
+use rokit_core::text_encoding::TextEncoding;
+use rokit_core::transport::{self, BoxStream, Protocol, TransportCommand, TransportConfig, TransportEvent, TransportHandle};
+use rokit_core::udp_client::{UDP_LOCAL_IP_ANY, UDP_LOCAL_PORT_ANY};
+use smol::future;
+use smol::io::{AsyncBufReadExt, BufReader};
+use smol::stream::StreamExt;
+
+pub const EXIT_OK : i32 = 0;
+pub const EXIT_CONNECTION : i32 = 1;
+pub const EXIT_USAGE : i32 = 2;
+pub const EXIT_EXPECT : i32 = 3;
+
+const DEFAULT_TIMEOUT : Duration = Duration::from_secs(5);
+
+const USAGE : &str = "用法: rokit <tcp|tcp-server|udp|udp-server> <地址:端口> [选项]
+
+选项:
+  --send <文本>          发送文本, 支持转义, 可重复
+  --send-hex <HEX>       发送HEX, 如'0A 1B ff', 可重复
+  --expect <文本>        等待收到包含该文本的数据, 可重复
+  --expect-hex <HEX>     等待收到包含该HEX的数据, 可重复
+  --script <文件>        运行脚本文件, 可重复
+  --replay <文件>        回放JSON行日志或pcap/pcapng中客户端发送的数据, 可重复
+  --replay-stream <序号> 回放抓包中的第几个TCP/UDP流, 默认0
+  --replay-scale <倍率>  按原始间隔乘以倍率回放, 默认按原始间隔
+  --replay-wait          每条收到响应后再发送下一条
+  --timeout <秒>         每个--expect的等待时间, 默认5
+  --local <地址:端口>    UDP本地地址
+  --buffer <字节>        接收缓冲大小
+  --encoding <编码>      文本编码, 如UTF-8, GBK
+  --line-ending <换行>   文本结尾追加none, lf, cr或crlf
+  --no-escape            不解析文本中的转义
+  --hex                  以HEX显示收到的数据
+  --frame <分帧>         按帧显示和匹配收到的数据: raw, delim:<转义文本>, fixed:<长度>,
+                         len:<1|2|4>[:be|le[:偏移[:调整]]]
+  --checksum <校验>      发送时追加并校验收到的帧: sum8, xor, crc8, crc16-modbus,
+                         crc16-ccitt, crc32, 可加:<跳过开头>[:<跳过结尾>]
+  -i, --interactive      完成以上步骤后从标准输入逐行发送
+
+不带--send/--expect时进入交互模式, 标准输入结束时断开.
+退出码: 0成功, 1连接错误, 2参数错误, 3等待超时或脚本失败.";
+
+enum CliStep {
+    SendText(String),
+    SendHex(String),
+    ExpectText(String),
+    ExpectHex(String),
+    Script(String),
+    Replay(String),
+}
+
+enum CliNext {
+    Event(Option<TransportEvent>),
+    Line(Option<io::Result<String>>),
+    Timeout,
+}
+
+struct CliOptions {
+    protocol:Protocol,
+    addr:String,
+    local_ip:String,
+    local_port:String,
+    buffer_size:usize,
+    encoding:TextEncoding,
+    line_ending:LineEnding,
+    escape:bool,
+    hex_display:bool,
+    framing:Framing,
+    checksum:Checksum,
+    timeout:Duration,
+    interactive:bool,
+    replay_stream:usize,
+    replay_mode:ReplayMode,
+    replay_scale:f64,
+    steps:Vec<CliStep>,
+}
+
+struct Cli {
+    options:CliOptions,
+    protocol:Protocol,
+    handle:TransportHandle,
+    events:BoxStream<TransportEvent>,
+    received:VecDeque<Vec<u8>>,
+    framers:HashMap<SocketAddr, Framer>,
+    tcp_server_peers:Vec<SocketAddr>,
+    udp_server_peer:Option<SocketAddr>,
+}
+
+pub fn run(args:Vec<String>) -> i32 {
+    let options = match parse_args(args) {
+        Ok(Some(x)) => x,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return EXIT_OK;
+        },
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return EXIT_USAGE;
+        }
+    };
+    smol::block_on(run_options(options))
+}
+
+fn log(msg:String) {
+    print!("{}", generate_log(msg));
+    let _ = io::stdout().flush();
+}
+
+fn parse_protocol(name:&str) -> Option<Protocol> {
+    match name {
+        "tcp" => Some(Protocol::TcpClient),
+        "tcp-server" => Some(Protocol::TcpServer),
+        "udp" => Some(Protocol::UdpClient),
+        "udp-server" => Some(Protocol::UdpServer),
+        _ => None
+    }
+}
+
+fn split_addr(addr:&str) -> Result<(String, String), RokitError> {
+    match addr.rsplit_once(':') {
+        Some((ip, port)) if !ip.is_empty() => Ok((ip.to_string(), port.to_string())),
+        _ => Err(RokitError::addr_parse("地址格式错误, 格式为'地址:端口':".to_string() + addr))
+    }
+}
+
+fn parse_args(args:Vec<String>) -> Result<Option<CliOptions>, RokitError> {
+    let mut args = args.into_iter();
+    let protocol = match args.next() {
+        Some(ref x) if x == "-h" || x == "--help" => return Ok(None),
+        Some(x) => match parse_protocol(&x) {
+            Some(p) => p,
+            None => return Err(RokitError::decode("未知命令:".to_string() + x.as_str()))
+        },
+        None => return Ok(None)
+    };
+    let addr = match args.next() {
+        Some(x) => x,
+        None => return Err(RokitError::decode("缺少地址".to_string()))
+    };
+    let mut options = CliOptions{
+        protocol,
+        addr,
+        local_ip:String::from(UDP_LOCAL_IP_ANY),
+        local_port:String::from(UDP_LOCAL_PORT_ANY),
+        buffer_size:common::DEFAULT_BUFFER_SIZE,
+        encoding:TextEncoding::default(),
+        line_ending:LineEnding::default(),
+        escape:true,
+        hex_display:false,
+        framing:Framing::default(),
+        checksum:Checksum::default(),
+        timeout:DEFAULT_TIMEOUT,
+        interactive:false,
+        replay_stream:0,
+        replay_mode:ReplayMode::default(),
+        replay_scale:1.0,
+        steps:Vec::new(),
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--no-escape" => options.escape = false,
+            "--hex" => options.hex_display = true,
+            "-i" | "--interactive" => options.interactive = true,
+            "--replay-wait" => options.replay_mode = ReplayMode::WaitResponse,
+            _ => {
+                let value = match args.next() {
+                    Some(x) => x,
+                    None => return Err(RokitError::decode(format!("{}缺少参数", arg)))
+                };
+                match arg.as_str() {
+                    "--send" => options.steps.push(CliStep::SendText(value)),
+                    "--send-hex" => options.steps.push(CliStep::SendHex(value)),
+                    "--expect" => options.steps.push(CliStep::ExpectText(value)),
+                    "--expect-hex" => options.steps.push(CliStep::ExpectHex(value)),
+                    "--script" => options.steps.push(CliStep::Script(value)),
+                    "--replay" => options.steps.push(CliStep::Replay(value)),
+                    "--replay-stream" => {
+                        options.replay_stream = match value.parse::<usize>() {
+                            Ok(x) => x,
+                            Err(_) => return Err(RokitError::decode("流序号格式错误:".to_string() + value.as_str()))
+                        };
+                    },
+                    "--replay-scale" => {
+                        options.replay_scale = match value.parse::<f64>() {
+                            Ok(x) if x >= 0.0 && x.is_finite() => x,
+                            _ => return Err(RokitError::decode("倍率格式错误:".to_string() + value.as_str()))
+                        };
+                        if options.replay_mode == ReplayMode::Original {
+                            options.replay_mode = ReplayMode::Scaled;
+                        }
+                    },
+                    "--timeout" => {
+                        options.timeout = match value.parse::<f64>() {
+                            Ok(x) if x > 0.0 && x.is_finite() => Duration::from_secs_f64(x),
+                            _ => return Err(RokitError::decode("超时格式错误:".to_string() + value.as_str()))
+                        };
+                    },
+                    "--local" => {
+                        let (ip, port) = split_addr(&value)?;
+                        options.local_ip = ip;
+                        options.local_port = port;
+                    },
+                    "--buffer" => options.buffer_size = common::parse_buffer_size(value)?,
+                    "--frame" => options.framing = Framing::parse_spec(&value)?,
+                    "--checksum" => options.checksum = Checksum::parse_spec(&value)?,
+                    "--encoding" => {
+                        options.encoding = match TextEncoding::from_name(&value) {
+                            Some(x) => x,
+                            None => return Err(RokitError::decode("未知编码:".to_string() + value.as_str()))
+                        };
+                    },
+                    "--line-ending" => {
+                        options.line_ending = match LineEnding::from_name(&value) {
+                            Some(x) => x,
+                            None => return Err(RokitError::decode("未知换行:".to_string() + value.as_str()))
+                        };
+                    },
+                    _ => return Err(RokitError::decode("未知选项:".to_string() + arg.as_str()))
+                }
+            }
+        }
+    }
+    if options.steps.is_empty() {
+        options.interactive = true;
+    }
+    Ok(Some(options))
+}
+
+async fn run_options(options:CliOptions) -> i32 {
+    let socket_addr = match split_addr(&options.addr).and_then(|(ip, port)| common::parse_ip_port(ip, port)) {
+        Ok(x) => x,
+        Err(e) => {
+            log(e.to_string());
+            return EXIT_CONNECTION;
+        }
+    };
+    let protocol = options.protocol;
+    let mut events = transport::open(TransportConfig{
+        protocol,
+        socket_addr,
+        local_ip:options.local_ip.clone(),
+        local_port:options.local_port.clone(),
+        buffer_size:options.buffer_size
+    });
+    let handle = match events.next().await {
+        Some(TransportEvent::Connected(handle, local_addr, remote_addr)) => {
+            match protocol {
+                Protocol::TcpClient => log(format!("TCP连接:{} {}", remote_addr.ip(), remote_addr.port())),
+                Protocol::TcpServer => log(format!("TCP监听:{} {}", local_addr.ip(), local_addr.port())),
+                Protocol::UdpClient => log(format!("UDP连接:{} {} 本地:{} {}", remote_addr.ip(), remote_addr.port(),
+                    local_addr.ip(), local_addr.port())),
+                Protocol::UdpServer => log(format!("UDP监听:{} {}", local_addr.ip(), local_addr.port())),
+            }
+            handle
+        },
+        Some(TransportEvent::Error(e)) => {
+            log(e.to_string());
+            return EXIT_CONNECTION;
+        },
+        _ => return EXIT_CONNECTION
+    };
+    let mut cli = Cli{
+        options,
+        protocol,
+        handle,
+        events,
+        received:VecDeque::new(),
+        framers:HashMap::new(),
+        tcp_server_peers:Vec::new(),
+        udp_server_peer:None,
+    };
+    match cli.run().await {
+        Ok(_) => EXIT_OK,
+        Err(code) => code
+    }
+}
+
+impl Cli {
+    async fn run(&mut self) -> Result<(), i32> {
+        let steps = std::mem::take(&mut self.options.steps);
+        for step in steps {
+            match step {
+                CliStep::SendText(text) => {
+                    let buffer = self.parse(common::text_to_bytes(&text, self.options.encoding, self.options.escape, self.options.line_ending))?;
+                    let display = format!("{} [{}]", text, common::bytes_to_hex(&buffer));
+                    let trailer = self.options.line_ending.to_bytes(self.options.encoding).len();
+                    let (buffer, display) = self.parse(self.append_checksum(buffer, display, trailer))?;
+                    self.send(buffer, display)?;
+                },
+                CliStep::SendHex(hex) => {
+                    let buffer = self.parse(common::parse_hex(hex))?;
+                    let display = common::bytes_to_hex(&buffer);
+                    let (buffer, display) = self.parse(self.append_checksum(buffer, display, 0))?;
+                    self.send(buffer, display)?;
+                },
+                CliStep::ExpectText(text) => {
+                    let pattern = self.parse(common::text_to_bytes(&text, self.options.encoding, self.options.escape, LineEnding::None))?;
+                    self.expect(pattern, text).await?;
+                },
+                CliStep::ExpectHex(hex) => {
+                    let pattern = self.parse(common::parse_hex(hex))?;
+                    let display = common::bytes_to_hex(&pattern);
+                    self.expect(pattern, display).await?;
+                },
+                CliStep::Script(path) => self.script(path).await?,
+                CliStep::Replay(path) => self.replay(path).await?
+            }
+        }
+        if self.options.interactive {
+            self.interactive().await?;
+        }
+        self.close().await
+    }
+
+    fn parse<T>(&self, result:Result<T, RokitError>) -> Result<T, i32> {
+        match result {
+            Ok(x) => Ok(x),
+            Err(e) => {
+                log(e.to_string());
+                Err(EXIT_USAGE)
+            }
+        }
+    }
+
+    fn append_checksum(&self, mut buffer:Vec<u8>, display:String, trailer:usize) -> Result<(Vec<u8>, String), RokitError> {
+        if !self.options.checksum.append || !self.options.checksum.is_enabled() {
+            return Ok((buffer, display));
+        }
+        let sum = self.options.checksum.insert(&mut buffer, trailer)?;
+        Ok((buffer, format!("{} +{}[{}]", display, self.options.checksum.algorithm, common::bytes_to_hex(&sum))))
+    }
+
+    fn send(&mut self, buffer:Vec<u8>, display:String) -> Result<(), i32> {
+        let target = match self.protocol {
+            Protocol::TcpClient | Protocol::UdpClient => None,
+            Protocol::TcpServer if !self.tcp_server_peers.is_empty() => None,
+            Protocol::UdpServer if self.udp_server_peer.is_some() => self.udp_server_peer,
+            Protocol::TcpServer | Protocol::UdpServer => {
+                log("无客户端".to_string());
+                return Ok(());
+            }
+        };
+        if let Err(e) = self.handle.send(TransportCommand::Send(target, buffer.clone())) {
+            log(e.to_string());
+            return Err(EXIT_CONNECTION);
+        }
+        match target {
+            Some(addr) => log(format!("{}已发送{}字节到{}:{}", self.protocol.name(), buffer.len(), addr, display)),
+            None => log(format!("{}已发送{}字节:{}", self.protocol.name(), buffer.len(), display)),
+        }
+        Ok(())
+    }
+
+    // Without framing the pattern may span reads, with framing it must be
+    // inside one frame and frames that do not match are dropped.
+    fn take_match(&mut self, pattern:&[u8]) -> bool {
+        if self.options.framing.mode == FramingMode::Raw {
+            let mut stream : Vec<u8> = self.received.drain(..).flatten().collect();
+            let found = match common::find_bytes(&stream, pattern) {
+                Some(index) => {
+                    stream.drain(..index + pattern.len());
+                    true
+                },
+                None => false
+            };
+            if !stream.is_empty() {
+                self.received.push_back(stream);
+            }
+            return found;
+        }
+        while let Some(frame) = self.received.pop_front() {
+            if common::find_bytes(&frame, pattern).is_some() {
+                return true;
+            }
+        }
+        false
+    }
+
+    async fn expect(&mut self, pattern:Vec<u8>, display:String) -> Result<(), i32> {
+        let deadline = Instant::now() + self.options.timeout;
+        loop {
+            if self.take_match(&pattern) {
+                log(format!("匹配:{}", display));
+                return Ok(());
+            }
+            let events = &mut self.events;
+            let next = future::or(
+                async { CliNext::Event(events.next().await) },
+                async {
+                    smol::Timer::at(deadline).await;
+                    CliNext::Timeout
+                }
+            ).await;
+            match next {
+                CliNext::Event(event) => self.handle_event(event)?,
+                CliNext::Line(_) | CliNext::Timeout => {
+                    log(RokitError::timeout(format!("等待{}", display)).to_string());
+                    return Err(EXIT_EXPECT);
+                }
+            }
+        }
+    }
+
+    async fn script(&mut self, path:String) -> Result<(), i32> {
+        let text = match std::fs::read_to_string(&path) {
+            Ok(x) => x,
+            Err(e) => {
+                log(format!("脚本读取错误:{} {}", path, e));
+                return Err(EXIT_USAGE);
+            }
+        };
+        let script = self.parse(Script::parse(&text))?;
+        log(format!("脚本开始:{}", path));
+        let mut runner = ScriptRunner::new(script, self.options.encoding, self.options.framing.mode != FramingMode::Raw);
+        loop {
+            while let Some(frame) = self.received.pop_front() {
+                runner.receive(&frame);
+            }
+            for action in runner.poll(Instant::now()) {
+                match action {
+                    ScriptAction::Send(buffer, display) => self.send(buffer, display)?,
+                    ScriptAction::Log(msg) => log(msg),
+                    ScriptAction::Finished(true) => return Ok(()),
+                    ScriptAction::Finished(false) => return Err(EXIT_EXPECT)
+                }
+            }
+            let deadline = match runner.next_deadline() {
+                Some(x) => x,
+                None => return Ok(())
+            };
+            let events = &mut self.events;
+            let next = future::or(
+                async { CliNext::Event(events.next().await) },
+                async {
+                    smol::Timer::at(deadline).await;
+                    CliNext::Timeout
+                }
+            ).await;
+            if let CliNext::Event(event) = next {
+                self.handle_event(event)?;
+            }
+        }
+    }
+
+    async fn replay(&mut self, path:String) -> Result<(), i32> {
+        if self.protocol == Protocol::TcpServer || self.protocol == Protocol::UdpServer {
+            log("回放需要TCP或UDP客户端连接".to_string());
+            return Err(EXIT_USAGE);
+        }
+        let recording = self.parse(Recording::load(Path::new(&path), self.options.replay_stream))?;
+        log(format!("回放开始:{} {} {}", path, recording.description, self.options.replay_mode));
+        let mut runner = ReplayRunner::new(recording, self.options.replay_mode, self.options.replay_scale);
+        loop {
+            for action in runner.poll(Instant::now()) {
+                match action {
+                    ReplayAction::Send(buffer, display) => self.send(buffer, display)?,
+                    ReplayAction::Log(msg) => log(msg),
+                    ReplayAction::Finished => return Ok(())
+                }
+            }
+            let deadline = match runner.next_deadline() {
+                Some(x) => x,
+                None => return Ok(())
+            };
+            let received = self.received.len();
+            let events = &mut self.events;
+            let next = future::or(
+                async { CliNext::Event(events.next().await) },
+                async {
+                    smol::Timer::at(deadline).await;
+                    CliNext::Timeout
+                }
+            ).await;
+            if let CliNext::Event(event) = next {
+                self.handle_event(event)?;
+                if self.received.len() > received {
+                    runner.receive();
+                }
+            }
+        }
+    }
+
+    async fn interactive(&mut self) -> Result<(), i32> {
+        let mut lines = BufReader::new(smol::Unblock::new(io::stdin())).lines();
+        loop {
+            let events = &mut self.events;
+            let next = future::or(
+                async { CliNext::Event(events.next().await) },
+                async { CliNext::Line(lines.next().await) }
+            ).await;
+            match next {
+                CliNext::Event(event) => self.handle_event(event)?,
+                CliNext::Line(Some(Ok(line))) => {
+                    let buffer = match common::text_to_bytes(&line, self.options.encoding, self.options.escape, self.options.line_ending) {
+                        Ok(x) => x,
+                        Err(e) => {
+                            log(e.to_string());
+                            continue;
+                        }
+                    };
+                    let display = format!("{} [{}]", line, common::bytes_to_hex(&buffer));
+                    let trailer = self.options.line_ending.to_bytes(self.options.encoding).len();
+                    let (buffer, display) = match self.append_checksum(buffer, display, trailer) {
+                        Ok(x) => x,
+                        Err(e) => {
+                            log(e.to_string());
+                            continue;
+                        }
+                    };
+                    self.send(buffer, display)?;
+                },
+                CliNext::Line(_) | CliNext::Timeout => return Ok(())
+            }
+        }
+    }
+
+    async fn close(&mut self) -> Result<(), i32> {
+        let _ = self.handle.send(TransportCommand::Close);
+        while let Some(event) = self.events.next().await {
+            self.handle_event(Some(event))?;
+        }
+        Ok(())
+    }
+
+    fn handle_event(&mut self, event:Option<TransportEvent>) -> Result<(), i32> {
+        match event {
+            Some(TransportEvent::Received(addr, buffer)) => {
+                if self.protocol == Protocol::UdpServer {
+                    self.udp_server_peer = Some(addr);
+                }
+                let framing = self.options.framing.clone();
+                let trailer = framing.trailer();
+                let (frames, error) = match self.protocol {
+                    Protocol::TcpClient | Protocol::TcpServer => self.framers.entry(addr).or_insert_with(|| Framer::new(framing)).push(&buffer),
+                    Protocol::UdpClient | Protocol::UdpServer => (vec![buffer], None)
+                };
+                for frame in frames {
+                    let mut received = common::format_received(&frame, self.options.hex_display, self.options.encoding);
+                    if self.options.checksum.verify {
+                        if let Some(alert) = self.options.checksum.describe(&self.options.checksum.verify(&frame, &trailer)) {
+                            received = format!("{} [{}]", received, alert);
+                        }
+                    }
+                    match self.protocol {
+                        Protocol::TcpClient => log(format!("TCP收到:{}", received)),
+                        Protocol::TcpServer => log(format!("TCP收到[{}]:{}", addr, received)),
+                        Protocol::UdpClient | Protocol::UdpServer => log(format!("UDP收到[{}]:{}", addr, received)),
+                    }
+                    self.received.push_back(frame);
+                }
+                if let Some(e) = error {
+                    log(e.to_string());
+                }
+                Ok(())
+            },
+            Some(TransportEvent::PeerConnected(addr, _)) => {
+                log(format!("TCP新连接:{} {}", addr.ip(), addr.port()));
+                self.tcp_server_peers.push(addr);
+                Ok(())
+            },
+            Some(TransportEvent::PeerDisconnected(addr, e)) => {
+                if let Some(mut framer) = self.framers.remove(&addr) {
+                    let rest = framer.flush();
+                    if !rest.is_empty() {
+                        log(format!("未完成帧[{}]:{}", addr, common::bytes_to_hex(&rest)));
+                    }
+                }
+                log(e.to_string());
+                self.tcp_server_peers.retain(|a| *a != addr);
+                if self.udp_server_peer == Some(addr) {
+                    self.udp_server_peer = None;
+                }
+                Ok(())
+            },
+            Some(TransportEvent::Error(e)) => {
+                log(e.to_string());
+                Ok(())
+            },
+            Some(TransportEvent::Connected(..)) => Ok(()),
+            None => Err(EXIT_CONNECTION)
+        }
+    }
+}
+
@@ -0,0 +1,35 @@
+use std::any::TypeId;
+use std::hash::{Hash, Hasher};
+
+use iced::futures::stream::BoxStream;
+use iced::Subscription;
+use iced_native::subscription::Recipe;
+use rokit_core::transport::{self, TransportConfig, TransportEvent};
+
+pub fn connect(id:usize, generation:u64, config:TransportConfig) -> Subscription<TransportEvent> {
+    Subscription::from_recipe(Connection { id, generation, config })
+}
+
+struct Connection {
+    id:usize,
+    generation:u64,
+    config:TransportConfig,
+}
+
+impl<H, E> Recipe<H, E> for Connection
+where
+    H: Hasher,
+{
+    type Output = TransportEvent;
+
+    fn hash(&self, state: &mut H) {
+        TypeId::of::<Self>().hash(state);
+        self.id.hash(state);
+        self.generation.hash(state);
+        self.config.protocol.hash(state);
+    }
+
+    fn stream(self: Box<Self>, _input: BoxStream<'static, E>) -> BoxStream<'static, Self::Output> {
+        transport::open(self.config)
+    }
+}
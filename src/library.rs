@@ -0,0 +1,465 @@
+use std::path::Path;
+
+use iced::{button, pick_list, text_input,
+    Align, Button, Column, Element, HorizontalAlignment,
+    Length, PickList, Row, Subscription, Text, TextInput, VerticalAlignment};
+use iced::keyboard::{self, KeyCode};
+use iced_native::{event, subscription, Event};
+
+use rokit_core::command::{CommandLibrary, PayloadKind, QuickCommand};
+use rokit_core::common::LineEnding;
+use rokit_core::text_encoding::TextEncoding;
+
+use crate::FZFONT;
+
+const LIBRARY_SAVE_BUTTON_TEXT_ADD : &str = "添加命令";
+const LIBRARY_SAVE_BUTTON_TEXT_UPDATE : &str = "保存修改";
+const LIBRARY_SHORTCUT_MAX : usize = 9;
+
+#[derive(Default)]
+struct LibraryRow {
+    send_button_state: button::State,
+    up_button_state: button::State,
+    down_button_state: button::State,
+    edit_button_state: button::State,
+    delete_button_state: button::State,
+}
+
+pub struct Library {
+    library:CommandLibrary,
+    editing:Option<usize>,
+    status:String,
+
+    name_text_input: String,
+    name_text_input_state: text_input::State,
+    group_text_input: String,
+    group_text_input_state: text_input::State,
+    payload_text_input: String,
+    payload_text_input_state: text_input::State,
+
+    kind: PayloadKind,
+    kind_pick_list_state: pick_list::State<PayloadKind>,
+    encoding: TextEncoding,
+    encoding_pick_list_state: pick_list::State<TextEncoding>,
+    line_ending: LineEnding,
+    line_ending_pick_list_state: pick_list::State<LineEnding>,
+
+    save_button_state: button::State,
+    new_button_state: button::State,
+
+    file_text_input: String,
+    file_text_input_state: text_input::State,
+    import_button_state: button::State,
+    export_button_state: button::State,
+
+    rows:Vec<LibraryRow>,
+}
+
+#[derive(Debug, Clone)]
+pub enum LibraryMessage {
+    NameTextInput(String),
+    GroupTextInput(String),
+    PayloadTextInput(String),
+    KindPickList(PayloadKind),
+    EncodingPickList(TextEncoding),
+    LineEndingPickList(LineEnding),
+    SaveButton,
+    NewButton,
+
+    FileTextInput(String),
+    ImportButton,
+    ExportButton,
+
+    Send(usize),
+    Up(usize),
+    Down(usize),
+    Edit(usize),
+    Delete(usize),
+    Shortcut(usize),
+}
+
+fn shortcut(event:Event, status:event::Status) -> Option<LibraryMessage> {
+    if status == event::Status::Captured {
+        return None;
+    }
+    let (key_code, modifiers) = match event {
+        Event::Keyboard(keyboard::Event::KeyPressed{key_code, modifiers}) => (key_code, modifiers),
+        _ => return None
+    };
+    if !modifiers.is_command_pressed() {
+        return None;
+    }
+    let keys = [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4, KeyCode::Key5,
+        KeyCode::Key6, KeyCode::Key7, KeyCode::Key8, KeyCode::Key9];
+    keys.iter().position(|k| *k == key_code).map(LibraryMessage::Shortcut)
+}
+
+impl Library {
+    pub fn new() -> Self {
+        let (library, status) = match CommandLibrary::load() {
+            Ok(x) => (x, String::new()),
+            Err(e) => (CommandLibrary::default(), "命令库".to_string() + e.to_string().as_str())
+        };
+        Library{
+            library,
+            editing:None,
+            status,
+
+            name_text_input: String::new(),
+            name_text_input_state: text_input::State::new(),
+            group_text_input: String::new(),
+            group_text_input_state: text_input::State::new(),
+            payload_text_input: String::new(),
+            payload_text_input_state: text_input::State::new(),
+
+            kind: PayloadKind::default(),
+            kind_pick_list_state: pick_list::State::default(),
+            encoding: TextEncoding::default(),
+            encoding_pick_list_state: pick_list::State::default(),
+            line_ending: LineEnding::default(),
+            line_ending_pick_list_state: pick_list::State::default(),
+
+            save_button_state: button::State::new(),
+            new_button_state: button::State::new(),
+
+            file_text_input: String::new(),
+            file_text_input_state: text_input::State::new(),
+            import_button_state: button::State::new(),
+            export_button_state: button::State::new(),
+
+            rows:Vec::new(),
+        }
+    }
+
+    fn persist(&mut self) {
+        if let Err(e) = self.library.save() {
+            self.status = "命令库".to_string() + e.to_string().as_str();
+        }
+    }
+
+    fn clear_editor(&mut self) {
+        self.editing = None;
+        self.name_text_input.clear();
+        self.payload_text_input.clear();
+    }
+
+    fn editor_command(&self) -> QuickCommand {
+        QuickCommand{
+            name:self.name_text_input.trim().to_string(),
+            group:self.group_text_input.trim().to_string(),
+            kind:self.kind,
+            payload:self.payload_text_input.clone(),
+            encoding:self.encoding,
+            line_ending:self.line_ending,
+        }
+    }
+
+    fn save_editor(&mut self) {
+        let command = self.editor_command();
+        if command.name.is_empty() {
+            self.status = "命令名称不能为空".to_string();
+            return;
+        }
+        if let Err(e) = command.to_bytes() {
+            self.status = e.to_string();
+            return;
+        }
+        self.status = match self.editing {
+            Some(index) if index < self.library.commands.len() => {
+                self.library.commands[index] = command;
+                "命令已修改".to_string()
+            },
+            _ => {
+                self.library.commands.push(command);
+                "命令已添加".to_string()
+            }
+        };
+        self.clear_editor();
+        self.persist();
+    }
+
+    pub fn subscription(&self) -> Subscription<LibraryMessage> {
+        subscription::events_with(shortcut)
+    }
+
+    // Returns the command to send on the current session, if any.
+    pub fn update(&mut self, message: LibraryMessage) -> Option<QuickCommand> {
+        match message {
+            LibraryMessage::NameTextInput(s) => {
+                self.name_text_input = s;
+                None
+            },
+            LibraryMessage::GroupTextInput(s) => {
+                self.group_text_input = s;
+                None
+            },
+            LibraryMessage::PayloadTextInput(s) => {
+                self.payload_text_input = s;
+                None
+            },
+            LibraryMessage::KindPickList(kind) => {
+                self.kind = kind;
+                None
+            },
+            LibraryMessage::EncodingPickList(encoding) => {
+                self.encoding = encoding;
+                None
+            },
+            LibraryMessage::LineEndingPickList(line_ending) => {
+                self.line_ending = line_ending;
+                None
+            },
+            LibraryMessage::SaveButton => {
+                self.save_editor();
+                None
+            },
+            LibraryMessage::NewButton => {
+                self.clear_editor();
+                self.status.clear();
+                None
+            },
+            LibraryMessage::FileTextInput(s) => {
+                self.file_text_input = s;
+                None
+            },
+            LibraryMessage::ImportButton => {
+                self.status = match self.library.import(Path::new(self.file_text_input.trim())) {
+                    Ok((added, replaced)) => format!("已导入{}条命令, 替换{}条", added, replaced),
+                    Err(e) => "导入".to_string() + e.to_string().as_str()
+                };
+                self.persist();
+                None
+            },
+            LibraryMessage::ExportButton => {
+                self.status = match self.library.export(Path::new(self.file_text_input.trim())) {
+                    Ok(_) => format!("已导出{}条命令", self.library.commands.len()),
+                    Err(e) => "导出".to_string() + e.to_string().as_str()
+                };
+                None
+            },
+            LibraryMessage::Send(index) => {
+                self.library.commands.get(index).cloned()
+            },
+            LibraryMessage::Shortcut(position) => {
+                match self.library.display_order().get(position) {
+                    Some(index) => self.library.commands.get(*index).cloned(),
+                    None => None
+                }
+            },
+            LibraryMessage::Up(index) => {
+                self.library.move_up(index);
+                self.editing = None;
+                self.persist();
+                None
+            },
+            LibraryMessage::Down(index) => {
+                self.library.move_down(index);
+                self.editing = None;
+                self.persist();
+                None
+            },
+            LibraryMessage::Edit(index) => {
+                if let Some(command) = self.library.commands.get(index).cloned() {
+                    self.editing = Some(index);
+                    self.name_text_input = command.name;
+                    self.group_text_input = command.group;
+                    self.payload_text_input = command.payload;
+                    self.kind = command.kind;
+                    self.encoding = command.encoding;
+                    self.line_ending = command.line_ending;
+                }
+                None
+            },
+            LibraryMessage::Delete(index) => {
+                if index < self.library.commands.len() {
+                    let command = self.library.commands.remove(index);
+                    self.status = format!("命令已删除:{}", command.name);
+                    self.clear_editor();
+                    self.persist();
+                }
+                None
+            }
+        }
+    }
+
+    pub fn view(&mut self) -> Element<'_, LibraryMessage> {
+        let library_text = Text::new("命令库")
+            .font(FZFONT)
+            .size(20)
+            .width(Length::Fill)
+            .vertical_alignment(VerticalAlignment::Center)
+            .horizontal_alignment(HorizontalAlignment::Center);
+
+        let name_text_input = TextInput::new(&mut self.name_text_input_state, "名称",&self.name_text_input,LibraryMessage::NameTextInput)
+            .font(FZFONT)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let group_text_input = TextInput::new(&mut self.group_text_input_state, "分组",&self.group_text_input,LibraryMessage::GroupTextInput)
+            .font(FZFONT)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let kind_pick_list = PickList::new(&mut self.kind_pick_list_state, &PayloadKind::ALL[..], Some(self.kind), LibraryMessage::KindPickList)
+            .font(FZFONT)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let name_row = Row::new()
+            .push(name_text_input)
+            .push(group_text_input)
+            .push(kind_pick_list)
+            .align_items(Align::Center)
+            .spacing(2);
+
+        let payload_placeholder = match self.kind {
+            PayloadKind::Text => "msg\\r\\n",
+            PayloadKind::Hex => "0A 1B ff",
+        };
+        let payload_text_input = TextInput::new(&mut self.payload_text_input_state, payload_placeholder,&self.payload_text_input,LibraryMessage::PayloadTextInput)
+            .font(FZFONT)
+            .width(Length::Fill)
+            .padding(5);
+        let payload_row = Row::new()
+            .push(payload_text_input)
+            .align_items(Align::Center)
+            .spacing(2);
+
+        let encoding_pick_list = PickList::new(&mut self.encoding_pick_list_state, &TextEncoding::ALL[..], Some(self.encoding), LibraryMessage::EncodingPickList)
+            .font(FZFONT)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let line_ending_pick_list = PickList::new(&mut self.line_ending_pick_list_state, &LineEnding::ALL[..], Some(self.line_ending), LibraryMessage::LineEndingPickList)
+            .font(FZFONT)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let save_button_text = Text::new(if self.editing.is_some() { LIBRARY_SAVE_BUTTON_TEXT_UPDATE } else { LIBRARY_SAVE_BUTTON_TEXT_ADD })
+            .font(FZFONT)
+            .size(16)
+            .vertical_alignment(VerticalAlignment::Center)
+            .horizontal_alignment(HorizontalAlignment::Center);
+        let save_button = Button::new(&mut self.save_button_state, save_button_text)
+            .on_press(LibraryMessage::SaveButton)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let new_button_text = Text::new("清空")
+            .font(FZFONT)
+            .size(16)
+            .vertical_alignment(VerticalAlignment::Center)
+            .horizontal_alignment(HorizontalAlignment::Center);
+        let new_button = Button::new(&mut self.new_button_state, new_button_text)
+            .on_press(LibraryMessage::NewButton)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let option_row = Row::new()
+            .push(encoding_pick_list)
+            .push(line_ending_pick_list)
+            .push(save_button)
+            .push(new_button)
+            .align_items(Align::Center)
+            .spacing(2);
+
+        let file_text_input = TextInput::new(&mut self.file_text_input_state, "命令库文件",&self.file_text_input,LibraryMessage::FileTextInput)
+            .font(FZFONT)
+            .width(Length::FillPortion(2))
+            .padding(5);
+        let import_button_text = Text::new("导入")
+            .font(FZFONT)
+            .size(16)
+            .vertical_alignment(VerticalAlignment::Center)
+            .horizontal_alignment(HorizontalAlignment::Center);
+        let import_button = Button::new(&mut self.import_button_state, import_button_text)
+            .on_press(LibraryMessage::ImportButton)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let export_button_text = Text::new("导出")
+            .font(FZFONT)
+            .size(16)
+            .vertical_alignment(VerticalAlignment::Center)
+            .horizontal_alignment(HorizontalAlignment::Center);
+        let export_button = Button::new(&mut self.export_button_state, export_button_text)
+            .on_press(LibraryMessage::ExportButton)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let file_row = Row::new()
+            .push(file_text_input)
+            .push(import_button)
+            .push(export_button)
+            .align_items(Align::Center)
+            .spacing(2);
+
+        let status_text = Text::new(&self.status)
+            .font(FZFONT)
+            .size(16)
+            .width(Length::Fill)
+            .horizontal_alignment(HorizontalAlignment::Left);
+
+        let mut library_column = Column::new()
+            .push(library_text)
+            .push(name_row)
+            .push(payload_row)
+            .push(option_row)
+            .push(file_row)
+            .push(status_text)
+            .padding(16)
+            .spacing(12)
+            .align_items(Align::Start)
+            .width(Length::FillPortion(1))
+            .max_height(1000000)
+            .max_width(1000000);
+
+        self.rows.resize_with(self.library.commands.len(), LibraryRow::default);
+        let mut rows : Vec<Option<&mut LibraryRow>> = self.rows.iter_mut().map(Some).collect();
+        let mut current_group = None;
+        for (position, index) in self.library.display_order().into_iter().enumerate() {
+            let command = &self.library.commands[index];
+            let row_state = match rows[index].take() {
+                Some(x) => x,
+                None => continue
+            };
+            if current_group != Some(command.group.as_str()) {
+                current_group = Some(command.group.as_str());
+                let group_name = if command.group.is_empty() { "未分组" } else { command.group.as_str() };
+                let group_text = Text::new(group_name)
+                    .font(FZFONT)
+                    .size(17)
+                    .horizontal_alignment(HorizontalAlignment::Left);
+                library_column = library_column.push(group_text);
+            }
+            let command_label = if position < LIBRARY_SHORTCUT_MAX {
+                format!("{} Ctrl+{}", command.name, position + 1)
+            } else {
+                command.name.clone()
+            };
+            let command_text = Text::new(command_label)
+                .font(FZFONT)
+                .size(16)
+                .width(Length::FillPortion(2))
+                .vertical_alignment(VerticalAlignment::Center)
+                .horizontal_alignment(HorizontalAlignment::Left);
+            let row_buttons = [
+                (&mut row_state.send_button_state, "发送", LibraryMessage::Send(index)),
+                (&mut row_state.up_button_state, "上移", LibraryMessage::Up(index)),
+                (&mut row_state.down_button_state, "下移", LibraryMessage::Down(index)),
+                (&mut row_state.edit_button_state, "编辑", LibraryMessage::Edit(index)),
+                (&mut row_state.delete_button_state, "删除", LibraryMessage::Delete(index)),
+            ];
+            let mut command_row = Row::new()
+                .push(command_text)
+                .align_items(Align::Center)
+                .spacing(2);
+            for (state, label, message) in row_buttons {
+                let button_text = Text::new(label)
+                    .font(FZFONT)
+                    .size(15)
+                    .vertical_alignment(VerticalAlignment::Center)
+                    .horizontal_alignment(HorizontalAlignment::Center);
+                let button = Button::new(state, button_text)
+                    .on_press(message)
+                    .width(Length::FillPortion(1))
+                    .padding(5);
+                command_row = command_row.push(button);
+            }
+            library_column = library_column.push(command_row);
+        }
+
+        library_column.into()
+    }
+}
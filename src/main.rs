@@ -1,97 +1,104 @@
-mod tcp_client;
-mod udp_client;
-mod rokit_error;
-mod common;
-
-use chrono::Local;
-use rokit_error::RokitError;
-use tcp_client::TcpClient;
-use iced::{button, executor, scrollable, text_input,
-    Align, Application, Button, Command, Column, Clipboard, Element, Font, Settings, HorizontalAlignment, 
-    Length, Row, Scrollable, Text, TextInput, VerticalAlignment};
-use udp_client::{UdpClient, UdpClientReceive};
+mod cli;
+mod connection;
+mod library;
+mod modbus;
+mod repeat;
+mod services;
+mod session;
+
+use library::{Library, LibraryMessage};
+use rokit_core::profile::ProfileStore;
+use rokit_core::transport::TransportEvent;
+use services::{Services, ServicesMessage};
+use session::{Session, SessionMessage};
+use iced::{button, executor, pick_list, scrollable, text_input,
+    Align, Application, Button, Command, Column, Clipboard, Element, Font, Settings, HorizontalAlignment,
+    Length, PickList, Row, Scrollable, Subscription, Text, TextInput, VerticalAlignment};
 
 const FZFONT: Font = Font::External {
     name: "方正字体",
     bytes: include_bytes!("source/FZFWZhuZGDLHJW.TTF"),
 };
 
-const CLIENT_TCP_BUTTON_TEXT_CONNECT : &str = "TCP连接";
-const CLIENT_TCP_BUTTON_TEXT_DISCONNECT : &str = "TCP断开";
-const CLIENT_UDP_BUTTON_TEXT_CONNECT : &str = "UDP连接";
-const CLIENT_UDP_BUTTON_TEXT_DISCONNECT : &str = "UDP断开";
+struct Tab{
+    button_state: button::State,
+    session:Session,
+}
 
 struct Rokit{
+    tabs:Vec<Tab>,
+    current:usize,
+    next_id:usize,
 
-    client_ip_text_input_state: text_input::State,
-    client_ip_text_input: String,
-    client_port_text_input_state: text_input::State,
-    client_port_text_input: String,
-    client_tcp_button_text:String,
-    client_tcp_button_state: button::State,
-
-    client_udp_button_text:String,
-    client_udp_button_state: button::State,
-
-    client_buffer_text_input_state: text_input::State,
-    client_buffer_text_input: String,
-    client_send_button_text:String,
-    client_send_button_state: button::State,
+    tab_new_button_state: button::State,
+    tab_close_button_state: button::State,
+    tab_name_text_input_state: text_input::State,
 
-    client_ascii_buffer_text_input_state: text_input::State,
-    client_ascii_buffer_text_input: String,
-    client_ascii_send_button_text:String,
-    client_ascii_send_button_state: button::State,
+    library:Library,
+    services:Services,
 
-    client_output_text:String,
-    client_output_scrollable_state:scrollable::State,
+    profiles:ProfileStore,
+    profile_name_text_input: String,
+    profile_name_text_input_state: text_input::State,
+    profile_pick_list_state: pick_list::State<String>,
+    profile_save_button_state: button::State,
+    profile_delete_button_state: button::State,
 
     scrollable_state:scrollable::State,
-
-    tcp_client:Option<TcpClient>,
-    udp_client:Option<UdpClient>,
 }
 
 #[derive(Debug, Clone)]
-struct TcpClientResult {
-    result:String,
-    client:TcpClient
-}
+enum RokitMessage {
+    TabSelect(usize),
+    TabNew,
+    TabClose,
+    TabNameTextInput(String),
 
-#[derive(Debug, Clone)]
-struct UdpClientResult {
-    result:UdpClientReceive,
-    client:UdpClient
+    ProfileNameTextInput(String),
+    ProfilePickList(String),
+    ProfileSaveButton,
+    ProfileDeleteButton,
+
+    Session(usize, SessionMessage),
+
+    Library(LibraryMessage),
+    Services(ServicesMessage),
 }
 
-#[derive(Debug, Clone)]
-enum RokitMessage {
-    ClientIPTextInput(String),
-    ClientPortTextInput(String),
-    ClientTCPButton,
-    ClientUDPButton,
-
-    ClientBufferTextInput(String),
-    ClientASCIIBufferTextInput(String),
-    ClientSendButton,
-    ClientASCIISendButton,
-
-    ReadTcpClient(Result<TcpClientResult, RokitError>),
-    ReadUdpClient(Result<UdpClientResult, RokitError>),
+impl Tab {
+    fn new(id:usize) -> Self {
+        Tab{
+            button_state: button::State::new(),
+            session: Session::new(id),
+        }
+    }
 }
 
 impl Rokit {
-    async fn read_tcp_client(mut tcp_client: TcpClient) -> Result<TcpClientResult, RokitError>{
-        match tcp_client.read() {
-            Ok(s) => Ok(TcpClientResult{client:tcp_client, result:s}),
-            Err(e) => Err(e)
-        }
+    fn current_session(&mut self) -> Option<&mut Session> {
+        let current = self.current;
+        self.tabs.iter_mut().map(|t| &mut t.session).find(|s| s.id == current)
     }
 
-    async fn read_udp_client(mut udp_client: UdpClient) -> Result<UdpClientResult, RokitError>{
-        match udp_client.read() {
-            Ok(s) => Ok(UdpClientResult{client:udp_client, result:s}),
-            Err(e) => Err(e)
+    fn new_tab(&mut self) {
+        let id = self.next_id;
+        self.next_id += 1;
+        let mut tab = Tab::new(id);
+        tab.session.set_recent(self.profiles.recent.clone());
+        self.tabs.push(tab);
+        self.current = id;
+    }
+
+    fn save_profiles(&mut self) {
+        let res = self.profiles.save();
+        let recent = self.profiles.recent.clone();
+        for tab in self.tabs.iter_mut() {
+            tab.session.set_recent(recent.clone());
+        }
+        if let Err(e) = res {
+            if let Some(session) = self.current_session() {
+                session.log("配置".to_string() + e.to_string().as_str());
+            }
         }
     }
 }
@@ -102,37 +109,41 @@ impl Application for Rokit {
     type Flags = ();
 
     fn new(_flags: Self::Flags) -> (Rokit, Command<Self::Message>) {
+        let mut tab = Tab::new(0);
+        let profiles = match ProfileStore::load() {
+            Ok(x) => x,
+            Err(e) => {
+                tab.session.log("配置".to_string() + e.to_string().as_str());
+                ProfileStore::default()
+            }
+        };
+        tab.session.set_recent(profiles.recent.clone());
+        if let Some(profile) = profiles.last() {
+            tab.session.apply_profile(profile);
+        }
+        let profile_name_text_input = tab.session.profile_name().to_string();
         (
             Rokit{
-                client_ip_text_input_state: text_input::State::new(),
-                client_ip_text_input: String::from("127.0.0.1"),
-                client_port_text_input_state: text_input::State::new(),
-                client_port_text_input: String::from("8888"),
-                client_tcp_button_text:String::from(CLIENT_TCP_BUTTON_TEXT_CONNECT),
-                client_tcp_button_state: button::State::new(),
-
-                client_udp_button_text:String::from(CLIENT_UDP_BUTTON_TEXT_CONNECT),
-                client_udp_button_state: button::State::new(),
-
-                client_buffer_text_input_state: text_input::State::new(),
-                client_buffer_text_input: String::from(""),
-                client_send_button_text:String::from("发送(UTF-8)"),
-                client_send_button_state: button::State::new(),
-            
-                client_ascii_buffer_text_input_state: text_input::State::new(),
-                client_ascii_buffer_text_input: String::from(""),
-                client_ascii_send_button_text:String::from("发送(ASCII)"),
-                client_ascii_send_button_state: button::State::new(),
-            
-                client_output_text:String::from(""),
-                client_output_scrollable_state:scrollable::State::new(),
+                tabs:vec![tab],
+                current:0,
+                next_id:1,
 
-                scrollable_state: scrollable::State::new(),
+                tab_new_button_state: button::State::new(),
+                tab_close_button_state: button::State::new(),
+                tab_name_text_input_state: text_input::State::new(),
+
+                library: Library::new(),
+                services: Services::new(),
 
-                tcp_client:None,
-                udp_client:None,
+                profiles,
+                profile_name_text_input,
+                profile_name_text_input_state: text_input::State::new(),
+                profile_pick_list_state: pick_list::State::default(),
+                profile_save_button_state: button::State::new(),
+                profile_delete_button_state: button::State::new(),
 
-            }, 
+                scrollable_state: scrollable::State::new(),
+            },
             Command::none()
         )
     }
@@ -143,373 +154,232 @@ impl Application for Rokit {
 
     fn update(&mut self, message: Self::Message, _clipboard: &mut Clipboard) -> Command<Self::Message> {
         match message {
-
-            RokitMessage::ClientIPTextInput(s) => {
-                self.client_ip_text_input = s;
+            RokitMessage::TabSelect(id) => {
+                self.current = id;
                 Command::none()
             },
-            RokitMessage::ClientPortTextInput(s) => {
-                self.client_port_text_input = s;
+            RokitMessage::TabNew => {
+                self.new_tab();
                 Command::none()
             },
-            RokitMessage::ClientTCPButton => {
-                match self.tcp_client {
-                    Some(ref mut client) => {
-                        match client.disconnect() {
-                            Ok(_) => {},
-                            Err(e) => {
-                                self.client_output_text += generate_log(e.msg).as_str();
-                            }
-                        }
-                        self.tcp_client = None;
-                        self.client_tcp_button_text = String::from(CLIENT_TCP_BUTTON_TEXT_CONNECT);
-                        Command::none()
-                    },
-                    None => {
-                        match self.udp_client {
-                            Some(ref mut udp_client) => {
-                                udp_client.close();
-                                self.udp_client = None;
-                                self.client_udp_button_text = String::from(CLIENT_UDP_BUTTON_TEXT_CONNECT);
-                            },
-                            None => {}
-                        }
-                        let new_tcp_client = TcpClient::connect(self.client_ip_text_input.clone(), self.client_port_text_input.clone());
-                        match new_tcp_client {
-                            Ok(tcp_client) => {
-                                let tcp_client_clone = tcp_client.clone();
-                                self.client_output_text += generate_log(format!("TCP连接:{} {}", tcp_client.socket_addr.ip().to_string(), tcp_client.socket_addr.port())).as_str();
-                                self.tcp_client = Some(tcp_client);
-                                self.client_tcp_button_text = String::from(CLIENT_TCP_BUTTON_TEXT_DISCONNECT);
-                                Command::perform(Rokit::read_tcp_client(tcp_client_clone), RokitMessage::ReadTcpClient)
-                            },
-                            Err(e) => {
-                                self.client_output_text += generate_log(e.msg).as_str();
-                                Command::none()
-                            }
-                        }
+            RokitMessage::TabClose => {
+                if let Some(index) = self.tabs.iter().position(|t| t.session.id == self.current) {
+                    let mut tab = self.tabs.remove(index);
+                    tab.session.close_all();
+                    if self.tabs.is_empty() {
+                        self.new_tab();
+                    } else {
+                        self.current = self.tabs[index.min(self.tabs.len() - 1)].session.id;
                     }
                 }
+                Command::none()
             },
-            RokitMessage::ClientUDPButton => {
-                match self.udp_client {
-                    Some(ref mut udp_client) => {
-                        udp_client.close();
-                        self.udp_client = None;
-                        self.client_udp_button_text = String::from(CLIENT_UDP_BUTTON_TEXT_CONNECT);
-                        Command::none()
-                    },
-                    None => {
-                        match self.tcp_client {
-                            None => {},
-                            Some(ref mut tcp_client) => {
-                                match tcp_client.disconnect() {
-                                    Ok(_) => {},
-                                    Err(e) => {
-                                        self.client_output_text += generate_log(e.msg).as_str();
-                                    }
-                                }
-                                self.tcp_client = None;
-                                self.client_tcp_button_text = String::from(CLIENT_TCP_BUTTON_TEXT_CONNECT);
-                            }
-                        }
-                        let new_udp_client = UdpClient::connect(self.client_ip_text_input.clone(), self.client_port_text_input.clone());
-                        match new_udp_client {
-                            Ok(udp_client) => {
-                                let udp_client_clone = udp_client.clone();
-                                self.client_output_text += generate_log(format!("UDP连接:{} {}", udp_client.socket_addr.ip().to_string(), udp_client.socket_addr.port())).as_str();
-                                self.udp_client = Some(udp_client);
-                                self.client_udp_button_text = String::from(CLIENT_UDP_BUTTON_TEXT_DISCONNECT);
-                                Command::perform(Rokit::read_udp_client(udp_client_clone), RokitMessage::ReadUdpClient)
-                            },
-                            Err(e) => {
-                                self.client_output_text += generate_log(e.msg).as_str();
-                                Command::none()
-                            }
-                        }
-                    }
+            RokitMessage::TabNameTextInput(s) => {
+                if let Some(session) = self.current_session() {
+                    session.name = s;
                 }
-            },
-
-            RokitMessage::ClientBufferTextInput(s) => {
-                self.client_buffer_text_input = s;
                 Command::none()
             },
-            RokitMessage::ClientASCIIBufferTextInput(s) => {
-                self.client_ascii_buffer_text_input = s;
+            RokitMessage::ProfileNameTextInput(s) => {
+                self.profile_name_text_input = s;
                 Command::none()
             },
-            RokitMessage::ClientSendButton => {
-                match self.tcp_client {
-                    Some(ref mut client) => {
-                        match client.send(self.client_buffer_text_input.clone()) {
-                            Ok(x) => self.client_output_text += generate_log(format!("TCP已发送{}字节:{}", x, self.client_buffer_text_input)).as_str(),
-                            Err(e) => {
-                                self.client_output_text += generate_log(e.msg).as_str();
-                                match client.disconnect() {
-                                    Ok(_) => {},
-                                    Err(e) => {
-                                        self.client_output_text += generate_log(e.msg).as_str();
-                                    }
-                                }
-                                self.tcp_client = None;
-                                self.client_tcp_button_text = String::from(CLIENT_TCP_BUTTON_TEXT_CONNECT);
-                            }
-                        }
-                    },
-                    None => {
-                        match self.udp_client {
-                            Some(ref mut client) => {
-                                match client.send(self.client_buffer_text_input.clone()) {
-                                    Ok(x) => self.client_output_text += generate_log(format!("UDP已发送{}字节:{}", x, self.client_buffer_text_input)).as_str(),
-                                    Err(e) => {
-                                        self.client_output_text += generate_log(e.msg).as_str();
-                                        client.close();
-                                        self.udp_client = None;
-                                        self.client_udp_button_text = String::from(CLIENT_UDP_BUTTON_TEXT_CONNECT);
-                                    }
-                                }
-                            },
-                            None => {
-                                self.client_output_text +=  generate_log("无连接".to_string()).as_str();
-                            }
-                        } 
+            RokitMessage::ProfilePickList(name) => {
+                if let Some(profile) = self.profiles.get(&name).cloned() {
+                    self.profile_name_text_input = name;
+                    if let Some(session) = self.current_session() {
+                        session.apply_profile(&profile);
                     }
-                } 
+                }
                 Command::none()
             },
-            RokitMessage::ClientASCIISendButton => {
-                let res = common::ascii_to_utf_8(self.client_ascii_buffer_text_input.clone());
-                let str = match res {
-                    Ok(x) => x,
-                    Err(e) => {
-                        self.client_output_text += generate_log(e.msg).as_str();
-                        return  Command::none();
-                    }
-                };
-                match self.tcp_client {
-                    Some(ref mut client) => {
-                        match client.send(str.clone()) {
-                            Ok(x) => self.client_output_text += generate_log(format!("TCP已发送{}字节:{} => {}", x, self.client_ascii_buffer_text_input, str)).as_str(),
-                            Err(e) => {
-                                self.client_output_text += generate_log(e.msg).as_str();
-                                match client.disconnect() {
-                                    Ok(_) => {},
-                                    Err(e) => {
-                                        self.client_output_text += generate_log(e.msg).as_str();
-                                    }
-                                }
-                                self.tcp_client = None;
-                                self.client_tcp_button_text = String::from(CLIENT_TCP_BUTTON_TEXT_CONNECT);
-                            }
-                        }
+            RokitMessage::ProfileSaveButton => {
+                let name = self.profile_name_text_input.trim().to_string();
+                let profile = match self.current_session() {
+                    Some(session) if name.is_empty() => {
+                        session.log("配置名称不能为空".to_string());
+                        None
                     },
-                    None => {
-                        match self.udp_client {
-                            Some(ref mut client) => {
-                                match client.send(str.clone()) {
-                                    Ok(x) => self.client_output_text += generate_log(format!("UDP已发送{}字节:{} => {}", x, self.client_ascii_buffer_text_input, str)).as_str(),
-                                    Err(e) => {
-                                        self.client_output_text += generate_log(e.msg).as_str();
-                                        client.close();
-                                        self.udp_client = None;
-                                        self.client_udp_button_text = String::from(CLIENT_UDP_BUTTON_TEXT_CONNECT);
-                                    }
-                                }
-                            },
-                            None => {
-                                self.client_output_text +=  generate_log("无连接".to_string()).as_str();
-                            }
-                        } 
-                    }
-                } 
+                    Some(session) => {
+                        let profile = session.profile(&name);
+                        session.log(format!("已保存配置:{} {}", name, profile.label()));
+                        Some(profile)
+                    },
+                    None => None
+                };
+                if let Some(profile) = profile {
+                    self.profiles.upsert(profile);
+                    self.save_profiles();
+                }
                 Command::none()
             },
-            RokitMessage::ReadTcpClient(result) => {
-                match result {
-                    Ok(x) => {
-                        self.client_output_text += generate_log(format!("TCP收到:{}", x.result)).as_str();
-                        match self.tcp_client{
-                            Some(_) => {
-                                Command::perform(Rokit::read_tcp_client(x.client), RokitMessage::ReadTcpClient)
-                            },
-                            None => {
-                                Command::none()
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        self.client_output_text += generate_log(e.msg).as_str();
-
-                        match self.tcp_client.as_mut() {
-                            Some(client) => {
-                                match client.disconnect() {
-                                    Ok(_) => {},
-                                    Err(e) => {
-                                        self.client_output_text += generate_log(e.msg).as_str();
-                                    }
-                                }
-                                self.tcp_client = None;
-                                self.client_tcp_button_text = String::from(CLIENT_TCP_BUTTON_TEXT_CONNECT);
-                            },
-                            None => {}
-                        }
-                        Command::none()
+            RokitMessage::ProfileDeleteButton => {
+                let name = self.profile_name_text_input.trim().to_string();
+                if self.profiles.remove(&name) {
+                    if let Some(session) = self.current_session() {
+                        session.log(format!("已删除配置:{}", name));
                     }
+                    self.profile_name_text_input.clear();
+                    self.save_profiles();
                 }
+                Command::none()
             },
-            RokitMessage::ReadUdpClient(result) => {
-                match result {
-                    Ok(x) => {
-                        self.client_output_text += generate_log(format!("UDP收到:{}", x.result.result)).as_str();
-                        match self.udp_client{
-                            Some(_) => {
-                                Command::perform(Rokit::read_udp_client(x.client), RokitMessage::ReadUdpClient)
-                            },
-                            None => {
-                                Command::none()
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        self.client_output_text += generate_log(e.msg).as_str();
-
-                        match self.udp_client.as_mut() {
-                            Some(client) => {
-                                client.close();
-                                self.udp_client = None;
-                                self.client_udp_button_text = String::from(CLIENT_UDP_BUTTON_TEXT_CONNECT);
-                            },
-                            None => {}
-                        }
-                        Command::none()
+            RokitMessage::Session(id, message) => {
+                let connected = matches!(message, SessionMessage::Transport(_, TransportEvent::Connected(..)));
+                let (command, recent) = match self.tabs.iter_mut().map(|t| &mut t.session).find(|s| s.id == id) {
+                    Some(session) => {
+                        let command = session.update(message).map(move |m| RokitMessage::Session(id, m));
+                        let recent = if connected && session.is_connected() {
+                            Some(session.profile(session.profile_name()))
+                        } else {
+                            None
+                        };
+                        (command, recent)
+                    },
+                    None => (Command::none(), None)
+                };
+                if let Some(profile) = recent {
+                    self.profiles.touch_recent(profile);
+                    self.save_profiles();
+                }
+                command
+            },
+            RokitMessage::Library(message) => {
+                if let Some(command) = self.library.update(message) {
+                    if let Some(session) = self.current_session() {
+                        session.send_command(&command);
                     }
                 }
+                Command::none()
+            },
+            RokitMessage::Services(message) => {
+                self.services.update(message);
+                Command::none()
             }
         }
     }
 
-    fn view(&mut self) -> Element<Self::Message> {
-        
-        let client_text = Text::new("Socket客户端")
+    fn subscription(&self) -> Subscription<Self::Message> {
+        let sessions = Subscription::batch(self.tabs.iter().map(|t| {
+            t.session.subscription()
+                .with(t.session.id)
+                .map(|(id, m)| RokitMessage::Session(id, m))
+        }));
+        Subscription::batch(vec![sessions, self.library.subscription().map(RokitMessage::Library),
+            self.services.subscription().map(RokitMessage::Services)])
+    }
+
+    fn view(&mut self) -> Element<'_, Self::Message> {
+
+        let current = self.current;
+        let mut tab_row = Row::new()
+            .spacing(2)
+            .align_items(Align::Center);
+        let mut current_name = String::new();
+        let mut current_view = None;
+        for tab in self.tabs.iter_mut() {
+            let session = &mut tab.session;
+            let tab_text = if session.id == current { format!("[{}]", session.name) } else { session.name.clone() };
+            let tab_button_text = Text::new(tab_text)
+                .font(FZFONT)
+                .size(15)
+                .vertical_alignment(VerticalAlignment::Center)
+                .horizontal_alignment(HorizontalAlignment::Center);
+            let tab_button = Button::new(&mut tab.button_state, tab_button_text)
+                .on_press(RokitMessage::TabSelect(session.id))
+                .padding(5);
+            tab_row = tab_row.push(tab_button);
+            if session.id == current {
+                current_name = session.name.clone();
+                let id = session.id;
+                current_view = Some(session.view().map(move |m| RokitMessage::Session(id, m)));
+            }
+        }
+        let tab_new_button_text = Text::new("+")
             .font(FZFONT)
+            .size(15)
             .vertical_alignment(VerticalAlignment::Center)
             .horizontal_alignment(HorizontalAlignment::Center);
-
-        let client_ip_text_input = TextInput::new(&mut self.client_ip_text_input_state, "IP地址",&self.client_ip_text_input,RokitMessage::ClientIPTextInput)
-            .font(FZFONT)
-            .width(Length::FillPortion(1)) 
+        let tab_new_button = Button::new(&mut self.tab_new_button_state, tab_new_button_text)
+            .on_press(RokitMessage::TabNew)
             .padding(5);
-        let client_port_text_input = TextInput::new(&mut self.client_port_text_input_state, "端口",&self.client_port_text_input,RokitMessage::ClientPortTextInput)
+        tab_row = tab_row.push(tab_new_button);
+
+        let tab_name_text_input = TextInput::new(&mut self.tab_name_text_input_state, "会话名称",&current_name,RokitMessage::TabNameTextInput)
             .font(FZFONT)
-            .width(Length::FillPortion(1)) 
+            .width(Length::FillPortion(3))
             .padding(5);
-        let client_tcp_button_text = Text::new(&self.client_tcp_button_text)
+        let tab_close_button_text = Text::new("关闭会话")
             .font(FZFONT)
             .size(15)
             .vertical_alignment(VerticalAlignment::Center)
             .horizontal_alignment(HorizontalAlignment::Center);
-        let client_tcp_button = Button::new(&mut self.client_tcp_button_state, client_tcp_button_text)
-            .on_press(RokitMessage::ClientTCPButton)
+        let tab_close_button = Button::new(&mut self.tab_close_button_state, tab_close_button_text)
+            .on_press(RokitMessage::TabClose)
             .width(Length::FillPortion(1))
             .padding(5);
-        let client_udp_button_text = Text::new(&self.client_udp_button_text)
+        let tab_name_row = Row::new()
+            .push(tab_name_text_input)
+            .push(tab_close_button)
+            .align_items(Align::Center)
+            .spacing(2);
+
+        let profile_name_text_input = TextInput::new(&mut self.profile_name_text_input_state, "配置名称",&self.profile_name_text_input,RokitMessage::ProfileNameTextInput)
             .font(FZFONT)
-            .size(15)
-            .vertical_alignment(VerticalAlignment::Center)
-            .horizontal_alignment(HorizontalAlignment::Center);
-        let client_udp_button = Button::new(&mut self.client_udp_button_state, client_udp_button_text)
-            .on_press(RokitMessage::ClientUDPButton)
             .width(Length::FillPortion(1))
             .padding(5);
-        let client_row = Row::new()
-            .push(client_ip_text_input)
-            .push(client_port_text_input)
-            .push(client_tcp_button)
-            .push(client_udp_button)
-            .padding(0)
-            .spacing(2)
-            .align_items(Align::Center)
-            .max_height(1000000)
-            .max_width(1000000);
-
-        let client_buffer_text_input = TextInput::new(&mut self.client_buffer_text_input_state, "msg",&self.client_buffer_text_input,RokitMessage::ClientBufferTextInput)
+        let profile_pick_list = PickList::new(&mut self.profile_pick_list_state, self.profiles.names(), None, RokitMessage::ProfilePickList)
             .font(FZFONT)
-            .width(Length::FillPortion(3)) 
+            .width(Length::FillPortion(1))
             .padding(5);
-        let client_send_button_text = Text::new(&self.client_send_button_text)
+        let profile_save_button_text = Text::new("保存配置")
             .font(FZFONT)
-            .size(16)
+            .size(15)
             .vertical_alignment(VerticalAlignment::Center)
             .horizontal_alignment(HorizontalAlignment::Center);
-        let client_send_button = Button::new(&mut self.client_send_button_state, client_send_button_text)
-            .on_press(RokitMessage::ClientSendButton)
+        let profile_save_button = Button::new(&mut self.profile_save_button_state, profile_save_button_text)
+            .on_press(RokitMessage::ProfileSaveButton)
             .width(Length::FillPortion(1))
             .padding(5);
-        let client_buffer_row = Row::new()
-            .push(client_buffer_text_input)
-            .push(client_send_button)
-            .align_items(Align::Center)
-            .spacing(2);
-
-        
-        let client_ascii_buffer_text_input = TextInput::new(&mut self.client_ascii_buffer_text_input_state, "msg",&self.client_ascii_buffer_text_input,RokitMessage::ClientASCIIBufferTextInput)
-            .font(FZFONT)
-            .width(Length::FillPortion(3)) 
-            .padding(5);
-        let client_ascii_send_button_text = Text::new(&self.client_ascii_send_button_text)
+        let profile_delete_button_text = Text::new("删除配置")
             .font(FZFONT)
-            .size(16)
+            .size(15)
             .vertical_alignment(VerticalAlignment::Center)
             .horizontal_alignment(HorizontalAlignment::Center);
-        let client_ascii_send_button = Button::new(&mut self.client_ascii_send_button_state, client_ascii_send_button_text)
-            .on_press(RokitMessage::ClientASCIISendButton)
+        let profile_delete_button = Button::new(&mut self.profile_delete_button_state, profile_delete_button_text)
+            .on_press(RokitMessage::ProfileDeleteButton)
             .width(Length::FillPortion(1))
             .padding(5);
-        
-        let client_ascii_buffer_row = Row::new()
-            .push(client_ascii_buffer_text_input)
-            .push(client_ascii_send_button)
+        let profile_row = Row::new()
+            .push(profile_name_text_input)
+            .push(profile_pick_list)
+            .push(profile_save_button)
+            .push(profile_delete_button)
             .align_items(Align::Center)
             .spacing(2);
-        
-        let client_output_text = Text::new(&self.client_output_text)
-            .font(FZFONT)
-            .size(17)
-            .width(Length::Fill)
-            .vertical_alignment(VerticalAlignment::Top)
-            .horizontal_alignment(HorizontalAlignment::Left);
-
-        let client_output_scrollable = Scrollable::new(&mut self.client_output_scrollable_state)
-            .push(client_output_text)
-            .max_height(275);
-        
-        let client_column = Column::new()
-            .push(client_text)
-            .push(client_row)
-            .push(client_buffer_row)
-            .push(client_ascii_buffer_row)
-            .push(client_output_scrollable)
+
+        let mut column = Column::new()
+            .push(tab_row)
+            .push(tab_name_row)
+            .push(profile_row)
             .padding(16)
             .spacing(12)
             .align_items(Align::Start)
             .width(Length::FillPortion(1))
             .max_height(1000000)
-            .max_width(1000000); 
+            .max_width(1000000);
+        if let Some(view) = current_view {
+            column = column.push(view);
+        }
+        column = column.push(self.library.view().map(RokitMessage::Library));
+        column = column.push(self.services.view().map(RokitMessage::Services));
 
         Scrollable::new(&mut self.scrollable_state)
-            .push(client_column)
+            .push(column)
             .into()
     }
 }
 
-fn generate_log(msg:String) -> String {
-
-    let fmt = "%H:%M:%S";
-    let date_str = Local::now().format(fmt).to_string();
-    return date_str + " " + msg.as_str() + "\n";
-}
-
 fn generate_setting() -> Settings<()> {
     let mut setting = Settings::default();
     setting.window.size.0 = 400;
@@ -520,6 +390,9 @@ fn generate_setting() -> Settings<()> {
     setting
 }
 pub fn main() -> iced::Result {
+    let args : Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        std::process::exit(cli::run(args));
+    }
     Rokit::run(generate_setting())
 }
-
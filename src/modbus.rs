@@ -0,0 +1,448 @@
+use std::fmt;
+use std::time::Duration;
+
+use iced::{button, pick_list, scrollable, text_input,
+    Align, Button, Checkbox, Column, Element, HorizontalAlignment,
+    Length, PickList, Row, Scrollable, Text, TextInput, VerticalAlignment};
+
+use rokit_core::common;
+use rokit_core::modbus::{self, ModbusClient, ModbusFault, ModbusFunction, ModbusReply, ModbusRequest, ModbusResponse,
+    ModbusServed, ModbusServer, ModbusTable};
+use rokit_core::rokit_error::RokitError;
+
+use crate::FZFONT;
+
+const MODBUS_SERVER_TABLE_ROWS_MAX : u16 = 125;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ModbusRole {
+    #[default]
+    Client,
+    Server,
+}
+
+impl ModbusRole {
+    pub const ALL: [ModbusRole; 2] = [ModbusRole::Client, ModbusRole::Server];
+}
+
+impl fmt::Display for ModbusRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ModbusRole::Client => "主站",
+            ModbusRole::Server => "从站模拟",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+pub enum ModbusAction {
+    Request,
+    Log(String),
+}
+
+pub struct ModbusPanel {
+    enabled:bool,
+    role:ModbusRole,
+    role_pick_list_state: pick_list::State<ModbusRole>,
+    client:ModbusClient,
+    server:ModbusServer,
+    table_title:String,
+    table:Vec<(u16, String, String)>,
+
+    function: ModbusFunction,
+    function_pick_list_state: pick_list::State<ModbusFunction>,
+    unit_id_text_input: String,
+    unit_id_text_input_state: text_input::State,
+    address_text_input: String,
+    address_text_input_state: text_input::State,
+    quantity_text_input: String,
+    quantity_text_input_state: text_input::State,
+    values_text_input: String,
+    values_text_input_state: text_input::State,
+    send_button_state: button::State,
+    table_scrollable_state: scrollable::State,
+
+    server_table: ModbusTable,
+    server_table_pick_list_state: pick_list::State<ModbusTable>,
+    server_address_text_input: String,
+    server_address_text_input_state: text_input::State,
+    server_count_text_input: String,
+    server_count_text_input_state: text_input::State,
+    server_values_text_input: String,
+    server_values_text_input_state: text_input::State,
+    server_write_button_state: button::State,
+    server_fault_pick_list_state: pick_list::State<ModbusFault>,
+    server_delay_text_input: String,
+    server_delay_text_input_state: text_input::State,
+}
+
+#[derive(Debug, Clone)]
+pub enum ModbusMessage {
+    EnabledCheckbox(bool),
+    RolePickList(ModbusRole),
+    FunctionPickList(ModbusFunction),
+    UnitIdTextInput(String),
+    AddressTextInput(String),
+    QuantityTextInput(String),
+    ValuesTextInput(String),
+    SendButton,
+
+    ServerTablePickList(ModbusTable),
+    ServerAddressTextInput(String),
+    ServerCountTextInput(String),
+    ServerValuesTextInput(String),
+    ServerWriteButton,
+    ServerFaultPickList(ModbusFault),
+    ServerDelayTextInput(String),
+}
+
+impl ModbusPanel {
+    pub fn new() -> Self {
+        ModbusPanel{
+            enabled:false,
+            role:ModbusRole::default(),
+            role_pick_list_state: pick_list::State::default(),
+            client:ModbusClient::new(),
+            server:ModbusServer::new(),
+            table_title:String::new(),
+            table:Vec::new(),
+
+            function: ModbusFunction::default(),
+            function_pick_list_state: pick_list::State::default(),
+            unit_id_text_input: String::from("1"),
+            unit_id_text_input_state: text_input::State::new(),
+            address_text_input: String::from("0"),
+            address_text_input_state: text_input::State::new(),
+            quantity_text_input: String::from("10"),
+            quantity_text_input_state: text_input::State::new(),
+            values_text_input: String::new(),
+            values_text_input_state: text_input::State::new(),
+            send_button_state: button::State::new(),
+            table_scrollable_state: scrollable::State::new(),
+
+            server_table: ModbusTable::default(),
+            server_table_pick_list_state: pick_list::State::default(),
+            server_address_text_input: String::from("0"),
+            server_address_text_input_state: text_input::State::new(),
+            server_count_text_input: String::from("20"),
+            server_count_text_input_state: text_input::State::new(),
+            server_values_text_input: String::new(),
+            server_values_text_input_state: text_input::State::new(),
+            server_write_button_state: button::State::new(),
+            server_fault_pick_list_state: pick_list::State::default(),
+            server_delay_text_input: String::from("0"),
+            server_delay_text_input_state: text_input::State::new(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn role(&self) -> ModbusRole {
+        self.role
+    }
+
+    pub fn delay(&self) -> Result<Duration, RokitError> {
+        match self.server_delay_text_input.trim().parse::<u64>() {
+            Ok(x) => Ok(Duration::from_millis(x)),
+            Err(_) => Err(RokitError::decode("延迟格式错误:".to_string() + self.server_delay_text_input.as_str()))
+        }
+    }
+
+    pub fn serve(&mut self, frame:&[u8]) -> Result<ModbusServed, RokitError> {
+        self.server.handle(frame)
+    }
+
+    fn write_server_registers(&mut self) -> Result<String, RokitError> {
+        let address = modbus::parse_number("起始地址", &self.server_address_text_input)?;
+        let values = modbus::parse_values(self.server_table.is_bits(), &self.server_values_text_input)?;
+        self.server.registers.set(self.server_table, address, &values)?;
+        Ok(format!("Modbus{}地址{}已写入{}个值", self.server_table, address, values.len()))
+    }
+
+    fn server_rows(&self) -> Vec<(u16, String, String)> {
+        let address = match modbus::parse_number("起始地址", &self.server_address_text_input) {
+            Ok(x) => x,
+            Err(_) => return Vec::new()
+        };
+        let count = match modbus::parse_number("数量", &self.server_count_text_input) {
+            Ok(x) => x.min(MODBUS_SERVER_TABLE_ROWS_MAX),
+            Err(_) => return Vec::new()
+        };
+        let bits = self.server_table.is_bits();
+        self.server.registers.get(self.server_table, address, count).iter().enumerate()
+            .map(|(i, v)| (address.wrapping_add(i as u16), v.to_string(), if bits { String::new() } else { format!("{:04X}", v) }))
+            .collect()
+    }
+
+    pub fn reset(&mut self) {
+        self.client.clear();
+    }
+
+    // Builds the next request frame and the text to log for it.
+    pub fn request(&mut self) -> Result<(Vec<u8>, String), RokitError> {
+        let unit_id = modbus::parse_unit_id(&self.unit_id_text_input)?;
+        let request = ModbusRequest::new(self.function, &self.address_text_input, &self.quantity_text_input, &self.values_text_input)?;
+        let describe = request.describe();
+        let (transaction_id, buffer) = self.client.request(unit_id, request);
+        let display = format!("Modbus请求#{} 从站{} {} [{}]", transaction_id, unit_id, describe, common::bytes_to_hex(&buffer));
+        Ok((buffer, display))
+    }
+
+    pub fn receive(&mut self, frame:&[u8]) -> Result<ModbusReply, RokitError> {
+        let reply = self.client.response(frame)?;
+        let address = reply.request.address;
+        let rows : Option<Vec<(u16, String, String)>> = match reply.response {
+            ModbusResponse::Bits(ref bits) => Some(bits.iter().enumerate()
+                .map(|(i, b)| (address.wrapping_add(i as u16), if *b { "1" } else { "0" }.to_string(), String::new()))
+                .collect()),
+            ModbusResponse::Registers(ref registers) => Some(registers.iter().enumerate()
+                .map(|(i, v)| (address.wrapping_add(i as u16), v.to_string(), format!("{:04X}", v)))
+                .collect()),
+            ModbusResponse::Written(_, _) | ModbusResponse::Exception(_) => None
+        };
+        if let Some(rows) = rows {
+            self.table_title = format!("从站{} {} 地址{} 数量{}", reply.unit_id, reply.request.function, address, reply.request.quantity);
+            self.table = rows;
+        }
+        Ok(reply)
+    }
+
+    pub fn update(&mut self, message: ModbusMessage) -> Option<ModbusAction> {
+        match message {
+            ModbusMessage::EnabledCheckbox(b) => {
+                self.enabled = b;
+                self.client.clear();
+                None
+            },
+            ModbusMessage::RolePickList(role) => {
+                self.role = role;
+                self.client.clear();
+                None
+            },
+            ModbusMessage::FunctionPickList(function) => {
+                self.function = function;
+                None
+            },
+            ModbusMessage::UnitIdTextInput(s) => {
+                self.unit_id_text_input = s;
+                None
+            },
+            ModbusMessage::AddressTextInput(s) => {
+                self.address_text_input = s;
+                None
+            },
+            ModbusMessage::QuantityTextInput(s) => {
+                self.quantity_text_input = s;
+                None
+            },
+            ModbusMessage::ValuesTextInput(s) => {
+                self.values_text_input = s;
+                None
+            },
+            ModbusMessage::SendButton => Some(ModbusAction::Request),
+            ModbusMessage::ServerTablePickList(table) => {
+                self.server_table = table;
+                None
+            },
+            ModbusMessage::ServerAddressTextInput(s) => {
+                self.server_address_text_input = s;
+                None
+            },
+            ModbusMessage::ServerCountTextInput(s) => {
+                self.server_count_text_input = s;
+                None
+            },
+            ModbusMessage::ServerValuesTextInput(s) => {
+                self.server_values_text_input = s;
+                None
+            },
+            ModbusMessage::ServerWriteButton => match self.write_server_registers() {
+                Ok(msg) => Some(ModbusAction::Log(msg)),
+                Err(e) => Some(ModbusAction::Log(e.to_string()))
+            },
+            ModbusMessage::ServerFaultPickList(fault) => {
+                self.server.fault = fault;
+                None
+            },
+            ModbusMessage::ServerDelayTextInput(s) => {
+                self.server_delay_text_input = s;
+                None
+            },
+        }
+    }
+
+    pub fn view(&mut self) -> Element<'_, ModbusMessage> {
+        let (table_title, table) = match self.role {
+            ModbusRole::Client => (self.table_title.clone(), self.table.clone()),
+            ModbusRole::Server => (format!("{} 地址{}", self.server_table, self.server_address_text_input.trim()), self.server_rows()),
+        };
+
+        let enabled_checkbox = Checkbox::new(self.enabled, "Modbus TCP", ModbusMessage::EnabledCheckbox)
+            .font(FZFONT)
+            .text_size(16)
+            .width(Length::FillPortion(1));
+        let role_pick_list = PickList::new(&mut self.role_pick_list_state, &ModbusRole::ALL[..], Some(self.role), ModbusMessage::RolePickList)
+            .font(FZFONT)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let enabled_row = Row::new()
+            .push(enabled_checkbox)
+            .push(role_pick_list)
+            .align_items(Align::Center)
+            .spacing(2);
+        let mut modbus_column = Column::new()
+            .push(enabled_row)
+            .spacing(12)
+            .align_items(Align::Start)
+            .width(Length::Fill);
+        if !self.enabled {
+            return modbus_column.into();
+        }
+
+        match self.role {
+            ModbusRole::Client => {
+                let function_pick_list = PickList::new(&mut self.function_pick_list_state, &ModbusFunction::ALL[..], Some(self.function), ModbusMessage::FunctionPickList)
+                    .font(FZFONT)
+                    .width(Length::FillPortion(2))
+                    .padding(5);
+                let unit_id_text_input = TextInput::new(&mut self.unit_id_text_input_state, "从站地址",&self.unit_id_text_input,ModbusMessage::UnitIdTextInput)
+                    .font(FZFONT)
+                    .width(Length::FillPortion(1))
+                    .padding(5);
+                let address_text_input = TextInput::new(&mut self.address_text_input_state, "起始地址",&self.address_text_input,ModbusMessage::AddressTextInput)
+                    .font(FZFONT)
+                    .width(Length::FillPortion(1))
+                    .padding(5);
+                let function_row = Row::new()
+                    .push(function_pick_list)
+                    .push(unit_id_text_input)
+                    .push(address_text_input)
+                    .align_items(Align::Center)
+                    .spacing(2);
+
+                let value_input = if self.function.is_write() {
+                    let values_placeholder = if self.function.is_bits() { "写入值, 如1 0 1" } else { "写入值, 如100 0x1F" };
+                    TextInput::new(&mut self.values_text_input_state, values_placeholder,&self.values_text_input,ModbusMessage::ValuesTextInput)
+                } else {
+                    TextInput::new(&mut self.quantity_text_input_state, "数量",&self.quantity_text_input,ModbusMessage::QuantityTextInput)
+                };
+                let value_input = value_input
+                    .font(FZFONT)
+                    .width(Length::FillPortion(3))
+                    .padding(5);
+                let send_button_text = Text::new("发送请求")
+                    .font(FZFONT)
+                    .size(16)
+                    .vertical_alignment(VerticalAlignment::Center)
+                    .horizontal_alignment(HorizontalAlignment::Center);
+                let send_button = Button::new(&mut self.send_button_state, send_button_text)
+                    .on_press(ModbusMessage::SendButton)
+                    .width(Length::FillPortion(1))
+                    .padding(5);
+                let request_row = Row::new()
+                    .push(value_input)
+                    .push(send_button)
+                    .align_items(Align::Center)
+                    .spacing(2);
+
+                modbus_column = modbus_column
+                    .push(function_row)
+                    .push(request_row);
+            },
+            ModbusRole::Server => {
+                let server_table_pick_list = PickList::new(&mut self.server_table_pick_list_state, &ModbusTable::ALL[..], Some(self.server_table), ModbusMessage::ServerTablePickList)
+                    .font(FZFONT)
+                    .width(Length::FillPortion(2))
+                    .padding(5);
+                let server_address_text_input = TextInput::new(&mut self.server_address_text_input_state, "起始地址",&self.server_address_text_input,ModbusMessage::ServerAddressTextInput)
+                    .font(FZFONT)
+                    .width(Length::FillPortion(1))
+                    .padding(5);
+                let server_count_text_input = TextInput::new(&mut self.server_count_text_input_state, "显示数量",&self.server_count_text_input,ModbusMessage::ServerCountTextInput)
+                    .font(FZFONT)
+                    .width(Length::FillPortion(1))
+                    .padding(5);
+                let server_table_row = Row::new()
+                    .push(server_table_pick_list)
+                    .push(server_address_text_input)
+                    .push(server_count_text_input)
+                    .align_items(Align::Center)
+                    .spacing(2);
+
+                let server_values_placeholder = if self.server_table.is_bits() { "从起始地址写入, 如1 0 1" } else { "从起始地址写入, 如100 0x1F" };
+                let server_values_text_input = TextInput::new(&mut self.server_values_text_input_state, server_values_placeholder,&self.server_values_text_input,ModbusMessage::ServerValuesTextInput)
+                    .font(FZFONT)
+                    .width(Length::FillPortion(3))
+                    .padding(5);
+                let server_write_button_text = Text::new("写入")
+                    .font(FZFONT)
+                    .size(16)
+                    .vertical_alignment(VerticalAlignment::Center)
+                    .horizontal_alignment(HorizontalAlignment::Center);
+                let server_write_button = Button::new(&mut self.server_write_button_state, server_write_button_text)
+                    .on_press(ModbusMessage::ServerWriteButton)
+                    .width(Length::FillPortion(1))
+                    .padding(5);
+                let server_values_row = Row::new()
+                    .push(server_values_text_input)
+                    .push(server_write_button)
+                    .align_items(Align::Center)
+                    .spacing(2);
+
+                let server_fault_pick_list = PickList::new(&mut self.server_fault_pick_list_state, &ModbusFault::ALL[..], Some(self.server.fault), ModbusMessage::ServerFaultPickList)
+                    .font(FZFONT)
+                    .width(Length::FillPortion(2))
+                    .padding(5);
+                let server_delay_text_input = TextInput::new(&mut self.server_delay_text_input_state, "响应延迟毫秒",&self.server_delay_text_input,ModbusMessage::ServerDelayTextInput)
+                    .font(FZFONT)
+                    .width(Length::FillPortion(2))
+                    .padding(5);
+                let server_fault_row = Row::new()
+                    .push(server_fault_pick_list)
+                    .push(server_delay_text_input)
+                    .align_items(Align::Center)
+                    .spacing(2);
+
+                modbus_column = modbus_column
+                    .push(server_table_row)
+                    .push(server_values_row)
+                    .push(server_fault_row);
+            },
+        }
+        if table.is_empty() {
+            return modbus_column.into();
+        }
+
+        let table_title_text = Text::new(table_title)
+            .font(FZFONT)
+            .size(16)
+            .width(Length::Fill)
+            .horizontal_alignment(HorizontalAlignment::Left);
+        let mut table_scrollable = Scrollable::new(&mut self.table_scrollable_state)
+            .push(table_row("地址", "值", "HEX"))
+            .max_height(200);
+        for (address, value, hex) in table.iter() {
+            table_scrollable = table_scrollable.push(table_row(&address.to_string(), value, hex));
+        }
+        modbus_column
+            .push(table_title_text)
+            .push(table_scrollable)
+            .into()
+    }
+}
+
+fn table_row<'a>(address:&str, value:&str, hex:&str) -> Row<'a, ModbusMessage> {
+    let mut row = Row::new()
+        .align_items(Align::Center)
+        .spacing(2);
+    for cell in [address, value, hex] {
+        row = row.push(Text::new(cell)
+            .font(FZFONT)
+            .size(16)
+            .width(Length::FillPortion(1))
+            .horizontal_alignment(HorizontalAlignment::Left));
+    }
+    row
+}
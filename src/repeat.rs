@@ -0,0 +1,135 @@
+use std::fmt;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use rokit_core::rokit_error::RokitError;
+
+const REPEAT_JITTER_TICK : Duration = Duration::from_millis(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RepeatSource {
+    #[default]
+    Text,
+    Ascii,
+    Hex,
+}
+
+impl RepeatSource {
+    pub const ALL: [RepeatSource; 3] = [RepeatSource::Text, RepeatSource::Ascii, RepeatSource::Hex];
+}
+
+impl fmt::Display for RepeatSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            RepeatSource::Text => "文本",
+            RepeatSource::Ascii => "ASCII",
+            RepeatSource::Hex => "HEX",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RepeatSend {
+    pub payload:Vec<u8>,
+    pub display:String,
+    interval:Duration,
+    jitter:Duration,
+    count:Option<u32>,
+    sent:u32,
+    next_due:Instant,
+    seed:u64,
+}
+
+fn parse_millis(name:&str, input:&str) -> Result<u64, RokitError> {
+    match input.trim().parse::<u64>() {
+        Ok(x) => Ok(x),
+        Err(_) => Err(RokitError::decode(format!("{}格式错误:{}", name, input)))
+    }
+}
+
+impl RepeatSend {
+    pub fn new(interval:&str, count:&str, jitter:&str, payload:Vec<u8>, display:String) -> Result<Self, RokitError> {
+        let interval = match parse_millis("间隔", interval)? {
+            0 => return Err(RokitError::decode("间隔格式错误, 至少1毫秒".to_string())),
+            x => Duration::from_millis(x)
+        };
+        let count = match count.trim() {
+            "" | "0" => None,
+            x => match x.parse::<u32>() {
+                Ok(n) => Some(n),
+                Err(_) => return Err(RokitError::decode("次数格式错误:".to_string() + x))
+            }
+        };
+        let jitter = if jitter.trim().is_empty() {
+            Duration::ZERO
+        } else {
+            Duration::from_millis(parse_millis("抖动", jitter)?)
+        };
+        let seed = match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(x) => x.as_nanos() as u64 | 1,
+            Err(_) => 1
+        };
+        Ok(RepeatSend{
+            payload,
+            display,
+            interval,
+            jitter,
+            count,
+            sent:0,
+            next_due:Instant::now(),
+            seed,
+        })
+    }
+
+    pub fn tick(&self) -> Duration {
+        if self.jitter.is_zero() {
+            self.interval
+        } else {
+            self.interval.min(REPEAT_JITTER_TICK)
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        match self.count {
+            Some(count) => self.sent >= count,
+            None => false
+        }
+    }
+
+    pub fn progress(&self) -> String {
+        match self.count {
+            Some(count) => format!("已发送{}/{}", self.sent, count),
+            None => format!("已发送{}", self.sent)
+        }
+    }
+
+    // Without jitter every tick of the interval timer is a send, otherwise the
+    // timer ticks faster and sends once the randomized due time has passed.
+    pub fn poll(&mut self, now:Instant) -> bool {
+        if self.is_done() || (!self.jitter.is_zero() && now < self.next_due) {
+            return false;
+        }
+        self.sent += 1;
+        self.next_due = now + self.next_delay();
+        true
+    }
+
+    pub fn start(&mut self, now:Instant) -> bool {
+        self.sent = 0;
+        self.next_due = now;
+        self.poll(now)
+    }
+
+    fn next_delay(&mut self) -> Duration {
+        if self.jitter.is_zero() {
+            return self.interval;
+        }
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 7;
+        self.seed ^= self.seed << 17;
+        let jitter = self.jitter.as_millis() as u64;
+        let offset = self.seed % (jitter * 2 + 1);
+        let delay = self.interval.as_millis() as u64 + offset;
+        Duration::from_millis(delay.saturating_sub(jitter))
+    }
+}
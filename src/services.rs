@@ -0,0 +1,241 @@
+use std::time::{Duration, Instant};
+
+use iced::{button, pick_list, text_input,
+    Align, Button, Column, Element, HorizontalAlignment,
+    Length, PickList, Row, Subscription, Text, TextInput, VerticalAlignment};
+use iced::time;
+
+use rokit_core::common;
+use rokit_core::service::{Service, ServiceKind, ServiceProtocol};
+
+use crate::FZFONT;
+
+const SERVICE_TICK : Duration = Duration::from_secs(1);
+
+struct ServiceRow {
+    service:Service,
+    stop_button_state: button::State,
+    last_bytes:u64,
+    last_tick:Instant,
+    rate:u64,
+}
+
+pub struct Services {
+    rows:Vec<ServiceRow>,
+    status:String,
+
+    kind: ServiceKind,
+    kind_pick_list_state: pick_list::State<ServiceKind>,
+    protocol: ServiceProtocol,
+    protocol_pick_list_state: pick_list::State<ServiceProtocol>,
+    ip_text_input: String,
+    ip_text_input_state: text_input::State,
+    port_text_input: String,
+    port_text_input_state: text_input::State,
+    start_button_state: button::State,
+}
+
+#[derive(Debug, Clone)]
+pub enum ServicesMessage {
+    KindPickList(ServiceKind),
+    ProtocolPickList(ServiceProtocol),
+    IPTextInput(String),
+    PortTextInput(String),
+    StartButton,
+    Stop(usize),
+    Tick(Instant),
+}
+
+impl ServiceRow {
+    fn stats(&self) -> String {
+        let (connections, received, sent) = self.service.totals();
+        let count = match self.service.protocol {
+            ServiceProtocol::Tcp => format!("连接{}", connections),
+            ServiceProtocol::Udp => format!("数据包{}", connections),
+        };
+        format!("{} 收到{}字节 发送{}字节 {}KB/s", count, received, sent, self.rate / 1024)
+    }
+}
+
+impl Services {
+    pub fn new() -> Self {
+        let kind = ServiceKind::default();
+        Services{
+            rows:Vec::new(),
+            status:String::new(),
+
+            kind,
+            kind_pick_list_state: pick_list::State::default(),
+            protocol: ServiceProtocol::default(),
+            protocol_pick_list_state: pick_list::State::default(),
+            ip_text_input: String::from("0.0.0.0"),
+            ip_text_input_state: text_input::State::new(),
+            port_text_input: kind.default_port().to_string(),
+            port_text_input_state: text_input::State::new(),
+            start_button_state: button::State::new(),
+        }
+    }
+
+    fn start(&mut self) {
+        let addr = match common::parse_ip_port(self.ip_text_input.trim().to_string(), self.port_text_input.trim().to_string()) {
+            Ok(x) => x,
+            Err(e) => {
+                self.status = e.to_string();
+                return;
+            }
+        };
+        match Service::start(self.kind, self.protocol, addr) {
+            Ok(service) => {
+                self.status = format!("已启动:{}", service.describe());
+                self.rows.push(ServiceRow{
+                    service,
+                    stop_button_state: button::State::new(),
+                    last_bytes:0,
+                    last_tick:Instant::now(),
+                    rate:0,
+                });
+            },
+            Err(e) => self.status = e.to_string()
+        }
+    }
+
+    pub fn subscription(&self) -> Subscription<ServicesMessage> {
+        if self.rows.is_empty() {
+            Subscription::none()
+        } else {
+            time::every(SERVICE_TICK).map(ServicesMessage::Tick)
+        }
+    }
+
+    pub fn update(&mut self, message: ServicesMessage) {
+        match message {
+            ServicesMessage::KindPickList(kind) => {
+                if self.port_text_input.trim() == self.kind.default_port().to_string() {
+                    self.port_text_input = kind.default_port().to_string();
+                }
+                self.kind = kind;
+            },
+            ServicesMessage::ProtocolPickList(protocol) => self.protocol = protocol,
+            ServicesMessage::IPTextInput(s) => self.ip_text_input = s,
+            ServicesMessage::PortTextInput(s) => self.port_text_input = s,
+            ServicesMessage::StartButton => self.start(),
+            ServicesMessage::Stop(index) => {
+                if index < self.rows.len() {
+                    let row = self.rows.remove(index);
+                    self.status = format!("已停止:{}", row.service.describe());
+                }
+            },
+            ServicesMessage::Tick(now) => {
+                for row in self.rows.iter_mut() {
+                    let (_, received, sent) = row.service.totals();
+                    let elapsed = now.saturating_duration_since(row.last_tick).as_secs_f64();
+                    if elapsed > 0.0 {
+                        row.rate = ((received + sent - row.last_bytes) as f64 / elapsed) as u64;
+                    }
+                    row.last_bytes = received + sent;
+                    row.last_tick = now;
+                }
+            }
+        }
+    }
+
+    pub fn view(&mut self) -> Element<'_, ServicesMessage> {
+        let services_text = Text::new("测试服务")
+            .font(FZFONT)
+            .size(20)
+            .width(Length::Fill)
+            .vertical_alignment(VerticalAlignment::Center)
+            .horizontal_alignment(HorizontalAlignment::Center);
+
+        let kind_pick_list = PickList::new(&mut self.kind_pick_list_state, &ServiceKind::ALL[..], Some(self.kind), ServicesMessage::KindPickList)
+            .font(FZFONT)
+            .width(Length::FillPortion(2))
+            .padding(5);
+        let protocol_pick_list = PickList::new(&mut self.protocol_pick_list_state, &ServiceProtocol::ALL[..], Some(self.protocol), ServicesMessage::ProtocolPickList)
+            .font(FZFONT)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let kind_row = Row::new()
+            .push(kind_pick_list)
+            .push(protocol_pick_list)
+            .align_items(Align::Center)
+            .spacing(2);
+
+        let ip_text_input = TextInput::new(&mut self.ip_text_input_state, "监听地址",&self.ip_text_input,ServicesMessage::IPTextInput)
+            .font(FZFONT)
+            .width(Length::FillPortion(2))
+            .padding(5);
+        let port_text_input = TextInput::new(&mut self.port_text_input_state, "端口",&self.port_text_input,ServicesMessage::PortTextInput)
+            .font(FZFONT)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let start_button_text = Text::new("启动服务")
+            .font(FZFONT)
+            .size(16)
+            .vertical_alignment(VerticalAlignment::Center)
+            .horizontal_alignment(HorizontalAlignment::Center);
+        let start_button = Button::new(&mut self.start_button_state, start_button_text)
+            .on_press(ServicesMessage::StartButton)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let addr_row = Row::new()
+            .push(ip_text_input)
+            .push(port_text_input)
+            .push(start_button)
+            .align_items(Align::Center)
+            .spacing(2);
+
+        let status_text = Text::new(&self.status)
+            .font(FZFONT)
+            .size(16)
+            .width(Length::Fill)
+            .horizontal_alignment(HorizontalAlignment::Left);
+
+        let mut services_column = Column::new()
+            .push(services_text)
+            .push(kind_row)
+            .push(addr_row)
+            .push(status_text)
+            .padding(16)
+            .spacing(12)
+            .align_items(Align::Start)
+            .width(Length::FillPortion(1))
+            .max_height(1000000)
+            .max_width(1000000);
+
+        for (index, row) in self.rows.iter_mut().enumerate() {
+            let describe_text = Text::new(row.service.describe())
+                .font(FZFONT)
+                .size(16)
+                .width(Length::Fill)
+                .horizontal_alignment(HorizontalAlignment::Left);
+            let stats_text = Text::new(row.stats())
+                .font(FZFONT)
+                .size(15)
+                .width(Length::Fill)
+                .horizontal_alignment(HorizontalAlignment::Left);
+            let service_column = Column::new()
+                .push(describe_text)
+                .push(stats_text)
+                .spacing(2)
+                .width(Length::FillPortion(3));
+            let stop_button_text = Text::new("停止")
+                .font(FZFONT)
+                .size(16)
+                .vertical_alignment(VerticalAlignment::Center)
+                .horizontal_alignment(HorizontalAlignment::Center);
+            let stop_button = Button::new(&mut row.stop_button_state, stop_button_text)
+                .on_press(ServicesMessage::Stop(index))
+                .width(Length::FillPortion(1))
+                .padding(5);
+            let service_row = Row::new()
+                .push(service_column)
+                .push(stop_button)
+                .align_items(Align::Center)
+                .spacing(2);
+            services_column = services_column.push(service_row);
+        }
+
+        services_column.into()
+    }
+}
@@ -0,0 +1,2056 @@
+use std::collections::HashMap;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use iced::{button, pick_list, scrollable, text_input,
+    Align, Button, Checkbox, Color, Command, Column, Element, HorizontalAlignment,
+    Length, PickList, Row, Scrollable, Subscription, Text, TextInput, VerticalAlignment};
+use iced::time;
+
+use rokit_core::checksum::{Checksum, ChecksumAlgorithm};
+use rokit_core::command::QuickCommand;
+use rokit_core::common::{self, LineEnding};
+use rokit_core::framing::{Framer, Framing, FramingMode};
+use rokit_core::log::{LogDirection, LogFile, LogFormat, LogRecord};
+use rokit_core::modbus::ModbusFrame;
+use rokit_core::pcap::{self, Capture, CaptureEvent, CaptureProtocol};
+use rokit_core::profile::Profile;
+use rokit_core::replay::{Recording, ReplayAction, ReplayMode, ReplayRunner};
+use rokit_core::rokit_error::RokitError;
+use rokit_core::script::{Script, ScriptAction, ScriptRunner};
+use rokit_core::text_encoding::TextEncoding;
+use rokit_core::transport::{Protocol, TransportCommand, TransportConfig, TransportEvent, TransportHandle};
+use rokit_core::udp_client::{UDP_LOCAL_IP_ANY, UDP_LOCAL_PORT_ANY};
+
+use crate::{connection, FZFONT};
+use crate::modbus::{ModbusAction, ModbusMessage, ModbusPanel, ModbusRole};
+use crate::repeat::{RepeatSend, RepeatSource};
+
+const CLIENT_TCP_BUTTON_TEXT_CONNECT : &str = "TCP连接";
+const CLIENT_TCP_BUTTON_TEXT_DISCONNECT : &str = "TCP断开";
+const CLIENT_TCP_SERVER_BUTTON_TEXT_LISTEN : &str = "TCP监听";
+const CLIENT_TCP_SERVER_BUTTON_TEXT_STOP : &str = "停止监听";
+const CLIENT_UDP_BUTTON_TEXT_CONNECT : &str = "UDP连接";
+const CLIENT_UDP_BUTTON_TEXT_DISCONNECT : &str = "UDP断开";
+const CLIENT_UDP_SERVER_BUTTON_TEXT_LISTEN : &str = "UDP监听";
+const CLIENT_UDP_SERVER_BUTTON_TEXT_STOP : &str = "停止监听";
+const UDP_SERVER_PEER_MAX : usize = 16;
+const CLIENT_OUTPUT_ALERT_COLOR : Color = Color::from_rgb(0.85, 0.1, 0.1);
+const CLIENT_PEER_ALL : &str = "全部客户端";
+const TCP_CONNECT_RETRY_MAX : u32 = 3;
+const TCP_CONNECT_RETRY_DELAY : Duration = Duration::from_secs(1);
+const CLIENT_SCRIPT_BUTTON_TEXT_RUN : &str = "运行脚本";
+const CLIENT_SCRIPT_BUTTON_TEXT_STOP : &str = "停止脚本";
+const SCRIPT_TICK : Duration = Duration::from_millis(50);
+const CLIENT_REPEAT_BUTTON_TEXT_START : &str = "自动发送";
+const CLIENT_REPEAT_BUTTON_TEXT_STOP : &str = "停止发送";
+const CLIENT_REPLAY_BUTTON_TEXT_START : &str = "开始回放";
+const CLIENT_REPLAY_BUTTON_TEXT_STOP : &str = "停止回放";
+const REPLAY_TICK : Duration = Duration::from_millis(10);
+const CLIENT_LOG_FILE_BUTTON_TEXT_START : &str = "记录到文件";
+const CLIENT_LOG_FILE_BUTTON_TEXT_STOP : &str = "停止记录";
+
+pub struct Session{
+    pub id:usize,
+    pub name:String,
+
+    client_ip_text_input_state: text_input::State,
+    client_ip_text_input: String,
+    client_port_text_input_state: text_input::State,
+    client_port_text_input: String,
+    client_resolve_button_state: button::State,
+    client_resolved_pick_list_state: pick_list::State<String>,
+    client_resolved_addrs: Vec<SocketAddr>,
+    client_resolved_selected: Option<String>,
+    client_recent_pick_list_state: pick_list::State<String>,
+    client_recent:Vec<Profile>,
+    client_profile_name:String,
+    client_protocol:Protocol,
+    client_local_ip_text_input_state: text_input::State,
+    client_local_ip_text_input: String,
+    client_local_port_text_input_state: text_input::State,
+    client_local_port_text_input: String,
+    client_buffer_size_text_input_state: text_input::State,
+    client_buffer_size_text_input: String,
+    client_tcp_button_text:String,
+    client_tcp_button_state: button::State,
+
+    client_tcp_server_button_text:String,
+    client_tcp_server_button_state: button::State,
+
+    client_udp_button_text:String,
+    client_udp_button_state: button::State,
+
+    client_udp_server_button_text:String,
+    client_udp_server_button_state: button::State,
+
+    client_peer_pick_list_state: pick_list::State<String>,
+    client_peer_selected: Option<String>,
+
+    client_buffer_text_input_state: text_input::State,
+    client_buffer_text_input: String,
+    client_send_button_text:String,
+    client_send_button_state: button::State,
+
+    client_ascii_buffer_text_input_state: text_input::State,
+    client_ascii_buffer_text_input: String,
+    client_ascii_send_button_text:String,
+    client_ascii_send_button_state: button::State,
+
+    client_hex_buffer_text_input_state: text_input::State,
+    client_hex_buffer_text_input: String,
+    client_hex_send_button_text:String,
+    client_hex_send_button_state: button::State,
+
+    client_hex_display: bool,
+    client_encoding_pick_list_state: pick_list::State<TextEncoding>,
+    client_encoding: TextEncoding,
+    client_escape: bool,
+    client_line_ending_pick_list_state: pick_list::State<LineEnding>,
+    client_line_ending: LineEnding,
+
+    client_checksum: Checksum,
+    client_checksum_pick_list_state: pick_list::State<ChecksumAlgorithm>,
+    client_checksum_range_text_input_state: text_input::State,
+    client_checksum_range_text_input: String,
+
+    client_framing: Framing,
+    client_framing_mode_pick_list_state: pick_list::State<FramingMode>,
+    client_framing_mode: FramingMode,
+    client_framing_text_input_state: text_input::State,
+    client_framing_text_input: String,
+
+    client_repeat_interval_text_input_state: text_input::State,
+    client_repeat_interval_text_input: String,
+    client_repeat_count_text_input_state: text_input::State,
+    client_repeat_count_text_input: String,
+    client_repeat_jitter_text_input_state: text_input::State,
+    client_repeat_jitter_text_input: String,
+    client_repeat_source_pick_list_state: pick_list::State<RepeatSource>,
+    client_repeat_source: RepeatSource,
+    client_repeat_button_text:String,
+    client_repeat_button_state: button::State,
+
+    client_script_text_input_state: text_input::State,
+    client_script_text_input: String,
+    client_script_button_text:String,
+    client_script_button_state: button::State,
+
+    client_replay_path_text_input_state: text_input::State,
+    client_replay_path_text_input: String,
+    client_replay_stream_text_input_state: text_input::State,
+    client_replay_stream_text_input: String,
+    client_replay_mode_pick_list_state: pick_list::State<ReplayMode>,
+    client_replay_mode: ReplayMode,
+    client_replay_scale_text_input_state: text_input::State,
+    client_replay_scale_text_input: String,
+    client_replay_button_text:String,
+    client_replay_button_state: button::State,
+
+    client_log_path_text_input_state: text_input::State,
+    client_log_path_text_input: String,
+    client_log_save_button_state: button::State,
+    client_log_clear_button_state: button::State,
+    client_log_format_pick_list_state: pick_list::State<LogFormat>,
+    client_log_format: LogFormat,
+    client_log_max_size_text_input_state: text_input::State,
+    client_log_max_size_text_input: String,
+    client_log_daily: bool,
+    client_log_file_button_text:String,
+    client_log_file_button_state: button::State,
+
+    client_capture_path_text_input_state: text_input::State,
+    client_capture_path_text_input: String,
+    client_capture_export_button_state: button::State,
+    client_capture_clear_button_state: button::State,
+
+    client_output:Vec<(String, bool)>,
+    client_output_scrollable_state:scrollable::State,
+    log_file:Option<LogFile>,
+    capture:Capture,
+
+    transport:Option<TransportConfig>,
+    transport_generation:u64,
+    transport_handle:Option<TransportHandle>,
+    transport_local_addr:Option<SocketAddr>,
+    capture_local_addrs:HashMap<SocketAddr, SocketAddr>,
+    transport_retries:u32,
+    script:Option<ScriptRunner>,
+    replay:Option<ReplayRunner>,
+    repeat:Option<RepeatSend>,
+    repeat_generation:u64,
+    tcp_server_peers:Vec<SocketAddr>,
+    udp_server_peers:Vec<SocketAddr>,
+    framers:HashMap<SocketAddr, Framer>,
+    modbus:ModbusPanel,
+}
+
+#[derive(Debug, Clone)]
+pub enum SessionMessage {
+    ClientIPTextInput(String),
+    ClientPortTextInput(String),
+    ClientResolveButton,
+    ClientResolvedPickList(String),
+    ClientRecentPickList(String),
+    ClientLocalIPTextInput(String),
+    ClientLocalPortTextInput(String),
+    ClientBufferSizeTextInput(String),
+    ClientTCPButton,
+    ClientTCPServerButton,
+    ClientUDPButton,
+    ClientUDPServerButton,
+    ClientPeerPickList(String),
+
+    ClientBufferTextInput(String),
+    ClientASCIIBufferTextInput(String),
+    ClientHexBufferTextInput(String),
+    ClientSendButton,
+    ClientASCIISendButton,
+    ClientHexSendButton,
+    ClientHexDisplayCheckbox(bool),
+    ClientEncodingPickList(TextEncoding),
+    ClientEscapeCheckbox(bool),
+    ClientLineEndingPickList(LineEnding),
+    ClientChecksumPickList(ChecksumAlgorithm),
+    ClientChecksumRangeTextInput(String),
+    ClientChecksumAppendCheckbox(bool),
+    ClientChecksumVerifyCheckbox(bool),
+    ClientFramingModePickList(FramingMode),
+    ClientFramingTextInput(String),
+
+    ClientLogPathTextInput(String),
+    ClientLogSaveButton,
+    ClientLogClearButton,
+    ClientLogFormatPickList(LogFormat),
+    ClientLogMaxSizeTextInput(String),
+    ClientLogDailyCheckbox(bool),
+    ClientLogFileButton,
+
+    ClientCapturePathTextInput(String),
+    ClientCaptureExportButton,
+    ClientCaptureClearButton,
+    ClientRepeatIntervalTextInput(String),
+    ClientRepeatCountTextInput(String),
+    ClientRepeatJitterTextInput(String),
+    ClientRepeatSourcePickList(RepeatSource),
+    ClientRepeatButton,
+    RepeatTick(Instant),
+    ClientScriptTextInput(String),
+    ClientScriptButton,
+    ScriptTick(Instant),
+
+    ClientReplayPathTextInput(String),
+    ClientReplayStreamTextInput(String),
+    ClientReplayModePickList(ReplayMode),
+    ClientReplayScaleTextInput(String),
+    ClientReplayButton,
+    ReplayTick(Instant),
+    Modbus(ModbusMessage),
+    ModbusDelayedReply(u64, SocketAddr, Vec<u8>, String),
+
+    Transport(u64, TransportEvent),
+    TransportRetry(u64),
+}
+
+impl Session {
+    pub fn new(id:usize) -> Self {
+        Session{
+            id,
+            name:format!("会话{}", id + 1),
+            client_ip_text_input_state: text_input::State::new(),
+            client_ip_text_input: String::from("127.0.0.1"),
+            client_port_text_input_state: text_input::State::new(),
+            client_port_text_input: String::from("8888"),
+            client_resolve_button_state: button::State::new(),
+            client_resolved_pick_list_state: pick_list::State::default(),
+            client_resolved_addrs: Vec::new(),
+            client_resolved_selected: None,
+            client_recent_pick_list_state: pick_list::State::default(),
+            client_recent:Vec::new(),
+            client_profile_name:String::new(),
+            client_protocol:Protocol::TcpClient,
+            client_local_ip_text_input_state: text_input::State::new(),
+            client_local_ip_text_input: String::from(UDP_LOCAL_IP_ANY),
+            client_local_port_text_input_state: text_input::State::new(),
+            client_local_port_text_input: String::from(UDP_LOCAL_PORT_ANY),
+            client_buffer_size_text_input_state: text_input::State::new(),
+            client_buffer_size_text_input: common::DEFAULT_BUFFER_SIZE.to_string(),
+            client_tcp_button_text:String::from(CLIENT_TCP_BUTTON_TEXT_CONNECT),
+            client_tcp_button_state: button::State::new(),
+
+            client_tcp_server_button_text:String::from(CLIENT_TCP_SERVER_BUTTON_TEXT_LISTEN),
+            client_tcp_server_button_state: button::State::new(),
+
+            client_udp_button_text:String::from(CLIENT_UDP_BUTTON_TEXT_CONNECT),
+            client_udp_button_state: button::State::new(),
+
+            client_udp_server_button_text:String::from(CLIENT_UDP_SERVER_BUTTON_TEXT_LISTEN),
+            client_udp_server_button_state: button::State::new(),
+
+            client_peer_pick_list_state: pick_list::State::default(),
+            client_peer_selected: Some(String::from(CLIENT_PEER_ALL)),
+
+            client_buffer_text_input_state: text_input::State::new(),
+            client_buffer_text_input: String::from(""),
+            client_send_button_text:format!("发送({})", TextEncoding::default()),
+            client_send_button_state: button::State::new(),
+
+            client_ascii_buffer_text_input_state: text_input::State::new(),
+            client_ascii_buffer_text_input: String::from(""),
+            client_ascii_send_button_text:String::from("发送(ASCII)"),
+            client_ascii_send_button_state: button::State::new(),
+
+            client_hex_buffer_text_input_state: text_input::State::new(),
+            client_hex_buffer_text_input: String::from(""),
+            client_hex_send_button_text:String::from("发送(HEX)"),
+            client_hex_send_button_state: button::State::new(),
+
+            client_hex_display: false,
+            client_encoding_pick_list_state: pick_list::State::default(),
+            client_encoding: TextEncoding::default(),
+            client_escape: true,
+            client_line_ending_pick_list_state: pick_list::State::default(),
+            client_line_ending: LineEnding::default(),
+
+            client_checksum: Checksum{ append:true, verify:true, ..Checksum::default() },
+            client_checksum_pick_list_state: pick_list::State::default(),
+            client_checksum_range_text_input_state: text_input::State::new(),
+            client_checksum_range_text_input: String::from("0:0"),
+
+            client_framing: Framing::default(),
+            client_framing_mode_pick_list_state: pick_list::State::default(),
+            client_framing_mode: FramingMode::default(),
+            client_framing_text_input_state: text_input::State::new(),
+            client_framing_text_input: String::new(),
+
+            client_repeat_interval_text_input_state: text_input::State::new(),
+            client_repeat_interval_text_input: String::from("1000"),
+            client_repeat_count_text_input_state: text_input::State::new(),
+            client_repeat_count_text_input: String::from(""),
+            client_repeat_jitter_text_input_state: text_input::State::new(),
+            client_repeat_jitter_text_input: String::from("0"),
+            client_repeat_source_pick_list_state: pick_list::State::default(),
+            client_repeat_source: RepeatSource::default(),
+            client_repeat_button_text:String::from(CLIENT_REPEAT_BUTTON_TEXT_START),
+            client_repeat_button_state: button::State::new(),
+
+            client_script_text_input_state: text_input::State::new(),
+            client_script_text_input: String::from(""),
+            client_script_button_text:String::from(CLIENT_SCRIPT_BUTTON_TEXT_RUN),
+            client_script_button_state: button::State::new(),
+
+            client_replay_path_text_input_state: text_input::State::new(),
+            client_replay_path_text_input: String::from(""),
+            client_replay_stream_text_input_state: text_input::State::new(),
+            client_replay_stream_text_input: String::from("0"),
+            client_replay_mode_pick_list_state: pick_list::State::default(),
+            client_replay_mode: ReplayMode::default(),
+            client_replay_scale_text_input_state: text_input::State::new(),
+            client_replay_scale_text_input: String::from("1.0"),
+            client_replay_button_text:String::from(CLIENT_REPLAY_BUTTON_TEXT_START),
+            client_replay_button_state: button::State::new(),
+
+            client_log_path_text_input_state: text_input::State::new(),
+            client_log_path_text_input: String::from(""),
+            client_log_save_button_state: button::State::new(),
+            client_log_clear_button_state: button::State::new(),
+            client_log_format_pick_list_state: pick_list::State::default(),
+            client_log_format: LogFormat::default(),
+            client_log_max_size_text_input_state: text_input::State::new(),
+            client_log_max_size_text_input: String::from(""),
+            client_log_daily: false,
+            client_log_file_button_text:String::from(CLIENT_LOG_FILE_BUTTON_TEXT_START),
+            client_log_file_button_state: button::State::new(),
+
+            client_capture_path_text_input_state: text_input::State::new(),
+            client_capture_path_text_input: String::from(""),
+            client_capture_export_button_state: button::State::new(),
+            client_capture_clear_button_state: button::State::new(),
+
+            client_output:Vec::new(),
+            client_output_scrollable_state:scrollable::State::new(),
+            log_file:None,
+            capture:Capture::new(),
+
+            transport:None,
+            transport_generation:0,
+            transport_handle:None,
+            transport_local_addr:None,
+            capture_local_addrs:HashMap::new(),
+            transport_retries:0,
+            script:None,
+            replay:None,
+            repeat:None,
+            repeat_generation:0,
+            tcp_server_peers:Vec::new(),
+            udp_server_peers:Vec::new(),
+            framers:HashMap::new(),
+            modbus:ModbusPanel::new(),
+        }
+    }
+
+    pub fn log(&mut self, msg:String) {
+        self.log_record(LogRecord::info(msg));
+    }
+
+    fn log_data(&mut self, direction:LogDirection, peer:Option<SocketAddr>, buffer:&[u8], msg:String) {
+        self.log_record(LogRecord::new(direction, peer, Some(buffer), msg));
+    }
+
+    fn set_framing(&mut self, mode:FramingMode, args:String) {
+        self.client_framing_mode = mode;
+        self.client_framing_text_input = args;
+        if let Ok(framing) = Framing::from_args(mode, &self.client_framing_text_input) {
+            if framing != self.client_framing {
+                self.client_framing = framing;
+                self.framers.clear();
+            }
+        }
+    }
+
+    fn receive(&mut self, protocol:Protocol, addr:SocketAddr, buffer:Vec<u8>) -> Command<SessionMessage> {
+        self.capture_event(addr, CaptureEvent::Data(false, buffer.clone()));
+        if protocol == Protocol::UdpServer {
+            self.touch_udp_server_peer(addr);
+        }
+        let framing = if self.modbus.is_enabled() {
+            ModbusFrame::framing()
+        } else {
+            self.client_framing.clone()
+        };
+        let trailer = framing.trailer();
+        let (frames, error) = match protocol {
+            Protocol::TcpClient | Protocol::TcpServer => self.framers.entry(addr).or_insert_with(|| Framer::new(framing)).push(&buffer),
+            Protocol::UdpClient | Protocol::UdpServer => (vec![buffer], None)
+        };
+        let mut commands = Vec::new();
+        for frame in frames {
+            let received = if self.modbus.is_enabled() {
+                common::bytes_to_hex(&frame)
+            } else {
+                self.format_received(&frame)
+            };
+            let msg = match protocol {
+                Protocol::TcpClient => format!("TCP收到:{}", received),
+                Protocol::TcpServer => format!("TCP收到[{}]:{}", addr, received),
+                Protocol::UdpClient | Protocol::UdpServer => format!("UDP收到[{}]:{}", addr, received),
+            };
+            let alert = if self.client_checksum.verify && self.client_checksum.is_enabled() {
+                match self.checksum() {
+                    Ok(checksum) => checksum.describe(&checksum.verify(&frame, &trailer)),
+                    Err(e) => Some(e.to_string())
+                }
+            } else {
+                None
+            };
+            match alert {
+                Some(alert) => self.output_record(LogRecord::new(LogDirection::Receive, Some(addr), Some(&frame), format!("{} [{}]", msg, alert)), true),
+                None => self.log_data(LogDirection::Receive, Some(addr), &frame, msg)
+            }
+            if self.modbus.is_enabled() {
+                match (self.modbus.role(), protocol) {
+                    (ModbusRole::Client, Protocol::TcpClient) => match self.modbus.receive(&frame) {
+                        Ok(reply) => self.output_record(LogRecord::info(reply.describe()), reply.is_exception()),
+                        Err(e) => self.output_record(LogRecord::info(e.to_string()), true)
+                    },
+                    (ModbusRole::Server, Protocol::TcpServer) => commands.push(self.serve_modbus(addr, &frame)),
+                    _ => {}
+                }
+            }
+            if let Some(runner) = self.script.as_mut() {
+                runner.receive(&frame);
+                self.run_script(Instant::now());
+            }
+            if let Some(runner) = self.replay.as_mut() {
+                runner.receive();
+                self.run_replay(Instant::now());
+            }
+        }
+        if let Some(e) = error {
+            self.log(e.to_string());
+        }
+        Command::batch(commands)
+    }
+
+    fn flush_framer(&mut self, addr:SocketAddr) {
+        let rest = match self.framers.remove(&addr) {
+            Some(mut framer) => framer.flush(),
+            None => return
+        };
+        if !rest.is_empty() {
+            self.log_data(LogDirection::Receive, Some(addr), &rest, format!("未完成帧[{}]:{}", addr, common::bytes_to_hex(&rest)));
+        }
+    }
+
+    fn capture_event(&mut self, remote:SocketAddr, event:CaptureEvent) {
+        let protocol = match self.transport {
+            Some(ref config) => match config.protocol {
+                Protocol::TcpClient | Protocol::TcpServer => CaptureProtocol::Tcp,
+                Protocol::UdpClient | Protocol::UdpServer => CaptureProtocol::Udp,
+            },
+            None => return
+        };
+        let local = match self.capture_local_addrs.get(&remote) {
+            Some(x) => *x,
+            None => match self.transport_local_addr {
+                Some(x) => {
+                    let local = pcap::route_local_addr(x, remote);
+                    self.capture_local_addrs.insert(remote, local);
+                    local
+                },
+                None => return
+            }
+        };
+        self.capture.record(protocol, local, remote, event);
+    }
+
+    fn export_capture(&mut self) {
+        let path = self.client_capture_path_text_input.trim().to_string();
+        if path.is_empty() {
+            self.log("抓包文件为空".to_string());
+            return;
+        }
+        match self.capture.write_pcapng(Path::new(&path)) {
+            Ok(count) => self.log(format!("已导出{}个数据包:{}", count, path)),
+            Err(e) => self.log(e.to_string())
+        }
+    }
+
+    fn log_record(&mut self, record:LogRecord) {
+        self.output_record(record, false);
+    }
+
+    // Highlighted lines are kept as separate blocks so the view can color them.
+    fn output_record(&mut self, record:LogRecord, highlight:bool) {
+        self.append_output(record.to_line(LogFormat::Text), highlight);
+        let res = match self.log_file {
+            Some(ref mut log_file) => log_file.write(&record),
+            None => Ok(())
+        };
+        if let Err(e) = res {
+            self.stop_log_file();
+            self.append_output(LogRecord::info(e.to_string()).to_line(LogFormat::Text), false);
+        }
+    }
+
+    fn append_output(&mut self, line:String, highlight:bool) {
+        match self.client_output.last_mut() {
+            Some((text, false)) if !highlight => text.push_str(&line),
+            _ => self.client_output.push((line, highlight))
+        }
+    }
+
+    fn output_text(&self) -> String {
+        self.client_output.iter().map(|(text, _)| text.as_str()).collect()
+    }
+
+    fn save_log(&mut self) {
+        let path = self.client_log_path_text_input.trim().to_string();
+        if path.is_empty() {
+            self.log("日志文件为空".to_string());
+            return;
+        }
+        match fs::write(&path, self.output_text().as_bytes()) {
+            Ok(_) => self.log(format!("日志已保存:{}", path)),
+            Err(e) => self.log(format!("日志保存错误:{} {}", path, e))
+        }
+    }
+
+    fn start_log_file(&mut self) {
+        let path = self.client_log_path_text_input.trim().to_string();
+        if path.is_empty() {
+            self.log("日志文件为空".to_string());
+            return;
+        }
+        let max_size = match self.client_log_max_size_text_input.trim() {
+            "" | "0" => None,
+            x => match x.parse::<u64>() {
+                Ok(n) => Some(n * 1024 * 1024),
+                Err(_) => {
+                    self.log("日志大小格式错误:".to_string() + x);
+                    return;
+                }
+            }
+        };
+        match LogFile::open(Path::new(&path), self.client_log_format, max_size, self.client_log_daily) {
+            Ok(log_file) => {
+                let current = log_file.current_path();
+                self.log_file = Some(log_file);
+                self.client_log_file_button_text = String::from(CLIENT_LOG_FILE_BUTTON_TEXT_STOP);
+                self.log(format!("开始记录日志:{}", current.display()));
+            },
+            Err(e) => self.log(e.to_string())
+        }
+    }
+
+    fn stop_log_file(&mut self) {
+        self.log_file = None;
+        self.client_log_file_button_text = String::from(CLIENT_LOG_FILE_BUTTON_TEXT_START);
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.transport_handle.is_some()
+    }
+
+    pub fn profile(&self, name:&str) -> Profile {
+        Profile{
+            name:name.to_string(),
+            protocol:self.client_protocol,
+            ip:self.client_ip_text_input.trim().to_string(),
+            port:self.client_port_text_input.trim().to_string(),
+            local_ip:self.client_local_ip_text_input.clone(),
+            local_port:self.client_local_port_text_input.clone(),
+            buffer_size:common::parse_buffer_size(self.client_buffer_size_text_input.clone()).unwrap_or(common::DEFAULT_BUFFER_SIZE),
+            encoding:self.client_encoding,
+            line_ending:self.client_line_ending,
+            escape:self.client_escape,
+            hex_display:self.client_hex_display,
+            framing:self.client_framing.clone(),
+            checksum:self.client_checksum.clone(),
+        }
+    }
+
+    pub fn profile_name(&self) -> &str {
+        &self.client_profile_name
+    }
+
+    pub fn apply_profile(&mut self, profile:&Profile) {
+        self.client_profile_name = profile.name.clone();
+        self.client_protocol = profile.protocol;
+        self.client_ip_text_input = profile.ip.clone();
+        self.client_port_text_input = profile.port.clone();
+        self.client_resolved_addrs.clear();
+        self.client_resolved_selected = None;
+        self.client_local_ip_text_input = profile.local_ip.clone();
+        self.client_local_port_text_input = profile.local_port.clone();
+        self.client_buffer_size_text_input = profile.buffer_size.to_string();
+        self.client_encoding = profile.encoding;
+        self.client_send_button_text = format!("发送({})", profile.encoding);
+        self.client_line_ending = profile.line_ending;
+        self.client_escape = profile.escape;
+        self.client_hex_display = profile.hex_display;
+        self.client_checksum = profile.checksum.clone();
+        self.client_checksum_range_text_input = profile.checksum.range();
+        self.client_framing = profile.framing.clone();
+        self.client_framing_mode = profile.framing.mode;
+        self.client_framing_text_input = profile.framing.args();
+        self.framers.clear();
+        if profile.name.is_empty() {
+            self.log(format!("已加载:{}", profile.label()));
+        } else {
+            self.log(format!("已加载配置:{} {}", profile.name, profile.label()));
+        }
+    }
+
+    pub fn set_recent(&mut self, recent:Vec<Profile>) {
+        self.client_recent = recent;
+    }
+
+    fn resolve(&mut self) -> Result<(), RokitError> {
+        self.client_resolved_addrs.clear();
+        self.client_resolved_selected = None;
+        let addrs = common::resolve_ip_port(self.client_ip_text_input.clone(), self.client_port_text_input.clone())?;
+        let list : Vec<String> = addrs.iter().map(|a| a.to_string()).collect();
+        self.log(format!("解析{}:{}", self.client_ip_text_input.trim(), list.join(", ")));
+        self.client_resolved_selected = list.into_iter().next();
+        self.client_resolved_addrs = addrs;
+        Ok(())
+    }
+
+    fn target_addr(&mut self) -> Result<SocketAddr, RokitError> {
+        if self.client_resolved_addrs.is_empty() {
+            self.resolve()?;
+        }
+        let selected = self.client_resolved_addrs.iter()
+            .find(|a| Some(a.to_string()) == self.client_resolved_selected)
+            .or_else(|| self.client_resolved_addrs.first());
+        match selected {
+            Some(x) => Ok(*x),
+            None => Err(RokitError::addr_parse("IP地址格式错误:".to_string() + self.client_ip_text_input.as_str()))
+        }
+    }
+
+    fn start(&mut self, protocol:Protocol) {
+        self.close_all();
+        if let Err(e) = Framing::from_args(self.client_framing_mode, &self.client_framing_text_input) {
+            self.log(e.to_string());
+            return;
+        }
+        let buffer_size = match common::parse_buffer_size(self.client_buffer_size_text_input.clone()) {
+            Ok(x) => x,
+            Err(e) => {
+                self.log(e.to_string());
+                return;
+            }
+        };
+        let socket_addr = match self.target_addr() {
+            Ok(x) => x,
+            Err(e) => {
+                self.log(e.to_string());
+                return;
+            }
+        };
+        self.transport_generation += 1;
+        self.transport_retries = 0;
+        self.client_protocol = protocol;
+        self.transport = Some(TransportConfig{
+            protocol,
+            socket_addr,
+            local_ip:self.client_local_ip_text_input.clone(),
+            local_port:self.client_local_port_text_input.clone(),
+            buffer_size
+        });
+        match protocol {
+            Protocol::TcpClient => self.client_tcp_button_text = String::from(CLIENT_TCP_BUTTON_TEXT_DISCONNECT),
+            Protocol::TcpServer => self.client_tcp_server_button_text = String::from(CLIENT_TCP_SERVER_BUTTON_TEXT_STOP),
+            Protocol::UdpClient => self.client_udp_button_text = String::from(CLIENT_UDP_BUTTON_TEXT_DISCONNECT),
+            Protocol::UdpServer => self.client_udp_server_button_text = String::from(CLIENT_UDP_SERVER_BUTTON_TEXT_STOP),
+        }
+    }
+
+    fn is_running(&self, protocol:Protocol) -> bool {
+        match self.transport {
+            Some(ref config) => config.protocol == protocol,
+            None => false
+        }
+    }
+
+    fn remove_peer(&mut self, addr:SocketAddr) {
+        self.tcp_server_peers.retain(|a| *a != addr);
+        self.udp_server_peers.retain(|a| *a != addr);
+        self.capture_local_addrs.remove(&addr);
+        if self.client_peer_selected == Some(addr.to_string()) {
+            self.client_peer_selected = Some(String::from(CLIENT_PEER_ALL));
+        }
+    }
+
+    fn touch_udp_server_peer(&mut self, addr:SocketAddr) {
+        self.udp_server_peers.retain(|a| *a != addr);
+        self.udp_server_peers.push(addr);
+        if self.udp_server_peers.len() > UDP_SERVER_PEER_MAX {
+            let removed = self.udp_server_peers.remove(0);
+            self.capture_local_addrs.remove(&removed);
+            if self.client_peer_selected == Some(removed.to_string()) {
+                self.client_peer_selected = Some(String::from(CLIENT_PEER_ALL));
+            }
+        }
+    }
+
+    fn start_script(&mut self) {
+        if self.transport_handle.is_none() {
+            self.log("无连接".to_string());
+            return;
+        }
+        let path = self.client_script_text_input.trim().to_string();
+        let text = match std::fs::read_to_string(&path) {
+            Ok(x) => x,
+            Err(e) => {
+                self.log(format!("脚本读取错误:{} {}", path, e));
+                return;
+            }
+        };
+        let script = match Script::parse(&text) {
+            Ok(x) => x,
+            Err(e) => {
+                self.log(e.to_string());
+                return;
+            }
+        };
+        self.log(format!("脚本开始:{}", path));
+        let framed = self.modbus.is_enabled() || self.client_framing.mode != FramingMode::Raw;
+        self.script = Some(ScriptRunner::new(script, self.client_encoding, framed));
+        self.client_script_button_text = String::from(CLIENT_SCRIPT_BUTTON_TEXT_STOP);
+        self.run_script(Instant::now());
+    }
+
+    fn run_script(&mut self, now:Instant) {
+        let actions = match self.script.as_mut() {
+            Some(runner) => runner.poll(now),
+            None => return
+        };
+        self.apply_script_actions(actions);
+    }
+
+    fn stop_script(&mut self) {
+        let actions = match self.script.as_mut() {
+            Some(runner) => runner.stop(),
+            None => return
+        };
+        self.apply_script_actions(actions);
+    }
+
+    fn apply_script_actions(&mut self, actions:Vec<ScriptAction>) {
+        for action in actions {
+            match action {
+                ScriptAction::Send(buffer, display) => self.send(buffer, display),
+                ScriptAction::Log(msg) => self.log(msg),
+                ScriptAction::Finished(_) => {
+                    self.script = None;
+                    self.client_script_button_text = String::from(CLIENT_SCRIPT_BUTTON_TEXT_RUN);
+                }
+            }
+        }
+    }
+
+    // Replays on the open TCP/UDP client connection. Without one, a client
+    // connection to the target address is opened first (UDP for UDP captures)
+    // and the replay starts once it is connected.
+    fn start_replay(&mut self) {
+        let connected = match self.transport {
+            Some(ref config) if config.protocol == Protocol::TcpClient || config.protocol == Protocol::UdpClient => true,
+            Some(_) => {
+                self.log("回放需要TCP或UDP客户端连接, 请先停止服务端".to_string());
+                return;
+            },
+            None => false
+        };
+        let stream = match self.client_replay_stream_text_input.trim().parse::<usize>() {
+            Ok(x) => x,
+            Err(_) => {
+                self.log("流序号格式错误:".to_string() + self.client_replay_stream_text_input.trim());
+                return;
+            }
+        };
+        let scale = match self.client_replay_scale_text_input.trim().parse::<f64>() {
+            Ok(x) if x >= 0.0 && x.is_finite() => x,
+            _ => {
+                self.log("倍率格式错误:".to_string() + self.client_replay_scale_text_input.trim());
+                return;
+            }
+        };
+        let path = self.client_replay_path_text_input.trim().to_string();
+        let recording = match Recording::load(Path::new(&path), stream) {
+            Ok(x) => x,
+            Err(e) => {
+                self.log(e.to_string());
+                return;
+            }
+        };
+        if !connected {
+            let protocol = match recording.protocol {
+                Some(CaptureProtocol::Udp) => Protocol::UdpClient,
+                Some(CaptureProtocol::Tcp) => Protocol::TcpClient,
+                None if self.client_protocol == Protocol::UdpClient => Protocol::UdpClient,
+                None => Protocol::TcpClient
+            };
+            self.start(protocol);
+            if self.transport.is_none() {
+                return;
+            }
+        }
+        self.log(format!("回放开始:{} {} {}", path, recording.description, self.client_replay_mode));
+        self.replay = Some(ReplayRunner::new(recording, self.client_replay_mode, scale));
+        self.client_replay_button_text = String::from(CLIENT_REPLAY_BUTTON_TEXT_STOP);
+        self.run_replay(Instant::now());
+    }
+
+    fn run_replay(&mut self, now:Instant) {
+        if self.transport_handle.is_none() {
+            return;
+        }
+        let actions = match self.replay.as_mut() {
+            Some(runner) => runner.poll(now),
+            None => return
+        };
+        self.apply_replay_actions(actions);
+    }
+
+    fn stop_replay(&mut self) {
+        let actions = match self.replay.as_mut() {
+            Some(runner) => runner.stop(),
+            None => return
+        };
+        self.apply_replay_actions(actions);
+    }
+
+    fn apply_replay_actions(&mut self, actions:Vec<ReplayAction>) {
+        for action in actions {
+            match action {
+                ReplayAction::Send(buffer, display) => self.send(buffer, display),
+                ReplayAction::Log(msg) => self.log(msg),
+                ReplayAction::Finished => {
+                    self.replay = None;
+                    self.client_replay_button_text = String::from(CLIENT_REPLAY_BUTTON_TEXT_START);
+                }
+            }
+        }
+    }
+
+    fn start_repeat(&mut self) {
+        if self.transport_handle.is_none() {
+            self.log("无连接".to_string());
+            return;
+        }
+        let (payload, display) = match self.payload(self.client_repeat_source) {
+            Ok(x) => x,
+            Err(e) => {
+                self.log(e.to_string());
+                return;
+            }
+        };
+        let mut repeat = match RepeatSend::new(&self.client_repeat_interval_text_input, &self.client_repeat_count_text_input,
+            &self.client_repeat_jitter_text_input, payload, display) {
+            Ok(x) => x,
+            Err(e) => {
+                self.log(e.to_string());
+                return;
+            }
+        };
+        self.log(format!("自动发送开始:间隔{}ms 次数{} 抖动{}ms", self.client_repeat_interval_text_input.trim(),
+            if self.client_repeat_count_text_input.trim().is_empty() { "无限" } else { self.client_repeat_count_text_input.trim() },
+            self.client_repeat_jitter_text_input.trim()));
+        let due = repeat.start(Instant::now());
+        let (payload, display) = (repeat.payload.clone(), repeat.display.clone());
+        self.repeat_generation += 1;
+        self.repeat = Some(repeat);
+        self.client_repeat_button_text = String::from(CLIENT_REPEAT_BUTTON_TEXT_STOP);
+        if due {
+            self.send(payload, display);
+        }
+        self.finish_repeat();
+    }
+
+    fn run_repeat(&mut self, now:Instant) {
+        let (payload, display) = match self.repeat.as_mut() {
+            Some(repeat) => {
+                if !repeat.poll(now) {
+                    return;
+                }
+                (repeat.payload.clone(), repeat.display.clone())
+            },
+            None => return
+        };
+        self.send(payload, display);
+        self.finish_repeat();
+    }
+
+    fn finish_repeat(&mut self) {
+        if let Some(repeat) = self.repeat.as_ref() {
+            if repeat.is_done() {
+                self.log(format!("自动发送完成:{}", repeat.progress()));
+                self.repeat = None;
+                self.client_repeat_button_text = String::from(CLIENT_REPEAT_BUTTON_TEXT_START);
+            }
+        }
+    }
+
+    fn stop_repeat(&mut self) {
+        if let Some(repeat) = self.repeat.take() {
+            self.log(format!("自动发送停止:{}", repeat.progress()));
+        }
+        self.client_repeat_button_text = String::from(CLIENT_REPEAT_BUTTON_TEXT_START);
+    }
+
+    fn send_modbus(&mut self) {
+        match self.transport {
+            Some(ref config) if config.protocol == Protocol::TcpClient && self.transport_handle.is_some() => {},
+            _ => {
+                self.log("Modbus需要TCP客户端连接".to_string());
+                return;
+            }
+        }
+        match self.modbus.request() {
+            Ok((buffer, display)) => self.send(buffer, display),
+            Err(e) => self.log(e.to_string())
+        }
+    }
+
+    fn serve_modbus(&mut self, addr:SocketAddr, frame:&[u8]) -> Command<SessionMessage> {
+        let served = match self.modbus.serve(frame) {
+            Ok(x) => x,
+            Err(e) => {
+                self.output_record(LogRecord::info(e.to_string()), true);
+                return Command::none();
+            }
+        };
+        self.output_record(LogRecord::info(served.message), served.exception.is_some());
+        let display = format!("Modbus响应 [{}]", common::bytes_to_hex(&served.response));
+        let delay = match self.modbus.delay() {
+            Ok(x) => x,
+            Err(e) => {
+                self.log(e.to_string());
+                Duration::ZERO
+            }
+        };
+        if delay.is_zero() {
+            self.reply(addr, served.response, display);
+            return Command::none();
+        }
+        let generation = self.transport_generation;
+        let response = served.response;
+        Command::perform(async move {
+            smol::Timer::after(delay).await;
+            (generation, addr, response, display)
+        }, |(generation, addr, response, display)| SessionMessage::ModbusDelayedReply(generation, addr, response, display))
+    }
+
+    fn reply(&mut self, addr:SocketAddr, buffer:Vec<u8>, display:String) {
+        let protocol = match self.transport {
+            Some(ref config) if self.transport_handle.is_some() => config.protocol,
+            _ => return
+        };
+        if protocol == Protocol::TcpServer && !self.tcp_server_peers.contains(&addr) {
+            self.log(format!("TCP客户端已断开:{}", addr));
+            return;
+        }
+        self.send_target(protocol, Some(addr), &buffer, &display);
+    }
+
+    pub fn close_all(&mut self) {
+        self.stop_script();
+        self.stop_replay();
+        self.stop_repeat();
+        let streams = match self.transport {
+            Some(ref config) if config.protocol == Protocol::TcpClient && self.transport_handle.is_some() => vec![config.socket_addr],
+            Some(ref config) if config.protocol == Protocol::TcpServer => self.tcp_server_peers.clone(),
+            _ => Vec::new()
+        };
+        for remote in streams {
+            self.capture_event(remote, CaptureEvent::Close);
+        }
+        let framed : Vec<SocketAddr> = self.framers.keys().cloned().collect();
+        for addr in framed {
+            self.flush_framer(addr);
+        }
+        if let (Some(config), Some(local_addr)) = (self.transport.take(), self.transport_local_addr.take()) {
+            match config.protocol {
+                Protocol::TcpServer | Protocol::UdpServer => {
+                    self.log(format!("{}停止监听:{} {}", config.protocol.name(), local_addr.ip(), local_addr.port()));
+                },
+                Protocol::TcpClient | Protocol::UdpClient => {}
+            }
+        }
+        self.transport_handle = None;
+        self.modbus.reset();
+        self.tcp_server_peers.clear();
+        self.udp_server_peers.clear();
+        self.capture_local_addrs.clear();
+        self.client_peer_selected = Some(String::from(CLIENT_PEER_ALL));
+        self.client_tcp_button_text = String::from(CLIENT_TCP_BUTTON_TEXT_CONNECT);
+        self.client_tcp_server_button_text = String::from(CLIENT_TCP_SERVER_BUTTON_TEXT_LISTEN);
+        self.client_udp_button_text = String::from(CLIENT_UDP_BUTTON_TEXT_CONNECT);
+        self.client_udp_server_button_text = String::from(CLIENT_UDP_SERVER_BUTTON_TEXT_LISTEN);
+    }
+
+    pub fn subscription(&self) -> Subscription<SessionMessage> {
+        match self.transport {
+            Some(ref config) => {
+                let mut subscriptions = vec![
+                    connection::connect(self.id, self.transport_generation, config.clone())
+                        .with(self.transport_generation)
+                        .map(|(generation, event)| SessionMessage::Transport(generation, event))
+                ];
+                if self.script.is_some() {
+                    subscriptions.push(time::every(SCRIPT_TICK).map(SessionMessage::ScriptTick));
+                }
+                if self.replay.is_some() {
+                    subscriptions.push(time::every(REPLAY_TICK).map(SessionMessage::ReplayTick));
+                }
+                if let Some(ref repeat) = self.repeat {
+                    subscriptions.push(time::every(repeat.tick())
+                        .with(self.repeat_generation)
+                        .map(|(_, now)| SessionMessage::RepeatTick(now)));
+                }
+                Subscription::batch(subscriptions)
+            },
+            None => Subscription::none()
+        }
+    }
+
+    fn peer_options(&self) -> Vec<String> {
+        let mut options = vec![String::from(CLIENT_PEER_ALL)];
+        options.extend(self.tcp_server_peers.iter().map(|a| a.to_string()));
+        options.extend(self.udp_server_peers.iter().rev().map(|a| a.to_string()));
+        options
+    }
+
+    fn selected_peers(&self, peers:Vec<SocketAddr>) -> Vec<SocketAddr> {
+        match self.client_peer_selected {
+            Some(ref selected) if selected != CLIENT_PEER_ALL => {
+                peers.into_iter().filter(|a| a.to_string() == *selected).collect()
+            },
+            _ => peers
+        }
+    }
+
+    fn format_received(&self, buffer:&[u8]) -> String {
+        common::format_received(buffer, self.client_hex_display, self.client_encoding)
+    }
+
+    fn payload(&self, source:RepeatSource) -> Result<(Vec<u8>, String), RokitError> {
+        let trailer = match source {
+            RepeatSource::Text => self.client_line_ending.to_bytes(self.client_encoding).len(),
+            RepeatSource::Ascii | RepeatSource::Hex => 0
+        };
+        let (buffer, display) = self.source_payload(source)?;
+        self.append_checksum(buffer, display, trailer)
+    }
+
+    // The checksum with the range currently typed in the text box.
+    fn checksum(&self) -> Result<Checksum, RokitError> {
+        let mut checksum = self.client_checksum.clone();
+        checksum.set_range(&self.client_checksum_range_text_input)?;
+        Ok(checksum)
+    }
+
+    fn append_checksum(&self, mut buffer:Vec<u8>, display:String, trailer:usize) -> Result<(Vec<u8>, String), RokitError> {
+        if !self.client_checksum.append || !self.client_checksum.is_enabled() {
+            return Ok((buffer, display));
+        }
+        let checksum = self.checksum()?;
+        let sum = checksum.insert(&mut buffer, trailer)?;
+        Ok((buffer, format!("{} +{}[{}]", display, checksum.algorithm, common::bytes_to_hex(&sum))))
+    }
+
+    fn source_payload(&self, source:RepeatSource) -> Result<(Vec<u8>, String), RokitError> {
+        match source {
+            RepeatSource::Text => {
+                let buffer = common::text_to_bytes(&self.client_buffer_text_input, self.client_encoding, self.client_escape, self.client_line_ending)?;
+                let display = format!("{} [{}]", self.client_buffer_text_input, common::bytes_to_hex(&buffer));
+                Ok((buffer, display))
+            },
+            RepeatSource::Ascii => {
+                let buffer = common::ascii_to_bytes(self.client_ascii_buffer_text_input.clone())?;
+                let display = format!("{} => {}", self.client_ascii_buffer_text_input, self.client_encoding.decode(&buffer));
+                Ok((buffer, display))
+            },
+            RepeatSource::Hex => {
+                let buffer = common::parse_hex(self.client_hex_buffer_text_input.clone())?;
+                let display = common::bytes_to_hex(&buffer);
+                Ok((buffer, display))
+            }
+        }
+    }
+
+    fn send_payload(&mut self, source:RepeatSource) {
+        match self.payload(source) {
+            Ok((buffer, display)) => self.send(buffer, display),
+            Err(e) => self.log(e.to_string())
+        }
+    }
+
+    pub fn send_command(&mut self, command:&QuickCommand) {
+        match command.to_bytes().and_then(|(buffer, display)| self.append_checksum(buffer, display, command.trailer_len())) {
+            Ok((buffer, display)) => self.send(buffer, display),
+            Err(e) => self.log(e.to_string())
+        }
+    }
+
+    fn send(&mut self, buffer:Vec<u8>, display:String) {
+        let protocol = match self.transport {
+            Some(ref config) if self.transport_handle.is_some() => config.protocol,
+            _ => {
+                self.log("无连接".to_string());
+                return;
+            }
+        };
+        let targets : Vec<Option<SocketAddr>> = match protocol {
+            Protocol::TcpClient | Protocol::UdpClient => vec![None],
+            Protocol::TcpServer => self.selected_peers(self.tcp_server_peers.clone()).into_iter().map(Some).collect(),
+            Protocol::UdpServer => self.selected_peers(self.udp_server_peers.clone()).into_iter().map(Some).collect(),
+        };
+        if targets.is_empty() {
+            self.log("无客户端".to_string());
+            return;
+        }
+        for target in targets {
+            if !self.send_target(protocol, target, &buffer, &display) {
+                return;
+            }
+        }
+    }
+
+    // Returns false when the transport was closed because of the error.
+    fn send_target(&mut self, protocol:Protocol, target:Option<SocketAddr>, buffer:&[u8], display:&str) -> bool {
+        let res = match self.transport_handle {
+            Some(ref handle) => handle.send(TransportCommand::Send(target, buffer.to_vec())),
+            None => return false
+        };
+        match res {
+            Ok(_) => {
+                let peer = match target {
+                    Some(addr) => addr,
+                    None => match self.transport {
+                        Some(ref config) => config.socket_addr,
+                        None => return false
+                    }
+                };
+                self.capture_event(peer, CaptureEvent::Data(true, buffer.to_vec()));
+                match target {
+                    Some(addr) => self.log_data(LogDirection::Send, Some(addr), buffer, format!("{}已发送{}字节到{}:{}", protocol.name(), buffer.len(), addr, display)),
+                    None => self.log_data(LogDirection::Send, Some(peer), buffer, format!("{}已发送{}字节:{}", protocol.name(), buffer.len(), display)),
+                }
+                true
+            },
+            Err(e) => {
+                self.log(e.to_string());
+                self.close_all();
+                false
+            }
+        }
+    }
+
+    pub fn update(&mut self, message: SessionMessage) -> Command<SessionMessage> {
+        match message {
+
+            SessionMessage::ClientIPTextInput(s) => {
+                self.client_ip_text_input = s;
+                self.client_resolved_addrs.clear();
+                self.client_resolved_selected = None;
+                Command::none()
+            },
+            SessionMessage::ClientPortTextInput(s) => {
+                self.client_port_text_input = s;
+                self.client_resolved_addrs.clear();
+                self.client_resolved_selected = None;
+                Command::none()
+            },
+            SessionMessage::ClientResolveButton => {
+                if let Err(e) = self.resolve() {
+                    self.log(e.to_string());
+                }
+                Command::none()
+            },
+            SessionMessage::ClientResolvedPickList(s) => {
+                self.client_resolved_selected = Some(s);
+                Command::none()
+            },
+            SessionMessage::ClientRecentPickList(s) => {
+                if let Some(profile) = self.client_recent.iter().find(|p| p.label() == s).cloned() {
+                    self.apply_profile(&profile);
+                }
+                Command::none()
+            },
+            SessionMessage::ClientLocalIPTextInput(s) => {
+                self.client_local_ip_text_input = s;
+                Command::none()
+            },
+            SessionMessage::ClientLocalPortTextInput(s) => {
+                self.client_local_port_text_input = s;
+                Command::none()
+            },
+            SessionMessage::ClientBufferSizeTextInput(s) => {
+                self.client_buffer_size_text_input = s;
+                Command::none()
+            },
+            SessionMessage::ClientTCPButton => {
+                if self.is_running(Protocol::TcpClient) {
+                    self.close_all();
+                } else {
+                    self.start(Protocol::TcpClient);
+                }
+                Command::none()
+            },
+            SessionMessage::ClientTCPServerButton => {
+                if self.is_running(Protocol::TcpServer) {
+                    self.close_all();
+                } else {
+                    self.start(Protocol::TcpServer);
+                }
+                Command::none()
+            },
+            SessionMessage::ClientUDPButton => {
+                if self.is_running(Protocol::UdpClient) {
+                    self.close_all();
+                } else {
+                    self.start(Protocol::UdpClient);
+                }
+                Command::none()
+            },
+            SessionMessage::ClientUDPServerButton => {
+                if self.is_running(Protocol::UdpServer) {
+                    self.close_all();
+                } else {
+                    self.start(Protocol::UdpServer);
+                }
+                Command::none()
+            },
+            SessionMessage::ClientPeerPickList(s) => {
+                self.client_peer_selected = Some(s);
+                Command::none()
+            },
+
+            SessionMessage::ClientBufferTextInput(s) => {
+                self.client_buffer_text_input = s;
+                Command::none()
+            },
+            SessionMessage::ClientASCIIBufferTextInput(s) => {
+                self.client_ascii_buffer_text_input = s;
+                Command::none()
+            },
+            SessionMessage::ClientHexBufferTextInput(s) => {
+                self.client_hex_buffer_text_input = s;
+                Command::none()
+            },
+            SessionMessage::ClientSendButton => {
+                self.send_payload(RepeatSource::Text);
+                Command::none()
+            },
+            SessionMessage::ClientASCIISendButton => {
+                self.send_payload(RepeatSource::Ascii);
+                Command::none()
+            },
+            SessionMessage::ClientHexSendButton => {
+                self.send_payload(RepeatSource::Hex);
+                Command::none()
+            },
+            SessionMessage::ClientHexDisplayCheckbox(b) => {
+                self.client_hex_display = b;
+                Command::none()
+            },
+            SessionMessage::ClientEncodingPickList(encoding) => {
+                self.client_encoding = encoding;
+                self.client_send_button_text = format!("发送({})", encoding);
+                Command::none()
+            },
+            SessionMessage::ClientEscapeCheckbox(b) => {
+                self.client_escape = b;
+                Command::none()
+            },
+            SessionMessage::ClientRepeatIntervalTextInput(s) => {
+                self.client_repeat_interval_text_input = s;
+                Command::none()
+            },
+            SessionMessage::ClientRepeatCountTextInput(s) => {
+                self.client_repeat_count_text_input = s;
+                Command::none()
+            },
+            SessionMessage::ClientRepeatJitterTextInput(s) => {
+                self.client_repeat_jitter_text_input = s;
+                Command::none()
+            },
+            SessionMessage::ClientRepeatSourcePickList(source) => {
+                self.client_repeat_source = source;
+                Command::none()
+            },
+            SessionMessage::ClientRepeatButton => {
+                if self.repeat.is_some() {
+                    self.stop_repeat();
+                } else {
+                    self.start_repeat();
+                }
+                Command::none()
+            },
+            SessionMessage::RepeatTick(now) => {
+                self.run_repeat(now);
+                Command::none()
+            },
+            SessionMessage::ClientScriptTextInput(s) => {
+                self.client_script_text_input = s;
+                Command::none()
+            },
+            SessionMessage::ClientScriptButton => {
+                if self.script.is_some() {
+                    self.stop_script();
+                } else {
+                    self.start_script();
+                }
+                Command::none()
+            },
+            SessionMessage::ScriptTick(now) => {
+                self.run_script(now);
+                Command::none()
+            },
+            SessionMessage::ClientReplayPathTextInput(s) => {
+                self.client_replay_path_text_input = s;
+                Command::none()
+            },
+            SessionMessage::ClientReplayStreamTextInput(s) => {
+                self.client_replay_stream_text_input = s;
+                Command::none()
+            },
+            SessionMessage::ClientReplayModePickList(mode) => {
+                self.client_replay_mode = mode;
+                Command::none()
+            },
+            SessionMessage::ClientReplayScaleTextInput(s) => {
+                self.client_replay_scale_text_input = s;
+                Command::none()
+            },
+            SessionMessage::ClientReplayButton => {
+                if self.replay.is_some() {
+                    self.stop_replay();
+                } else {
+                    self.start_replay();
+                }
+                Command::none()
+            },
+            SessionMessage::ReplayTick(now) => {
+                self.run_replay(now);
+                Command::none()
+            },
+            SessionMessage::ClientLineEndingPickList(line_ending) => {
+                self.client_line_ending = line_ending;
+                Command::none()
+            },
+            SessionMessage::Modbus(message) => {
+                let enabled = self.modbus.is_enabled();
+                match self.modbus.update(message) {
+                    Some(ModbusAction::Request) => self.send_modbus(),
+                    Some(ModbusAction::Log(msg)) => self.log(msg),
+                    None => {}
+                }
+                if enabled != self.modbus.is_enabled() {
+                    self.framers.clear();
+                }
+                Command::none()
+            },
+            SessionMessage::ModbusDelayedReply(generation, addr, buffer, display) => {
+                if self.transport.is_some() && generation == self.transport_generation {
+                    self.reply(addr, buffer, display);
+                }
+                Command::none()
+            },
+            SessionMessage::ClientChecksumPickList(algorithm) => {
+                self.client_checksum.algorithm = algorithm;
+                Command::none()
+            },
+            SessionMessage::ClientChecksumRangeTextInput(s) => {
+                self.client_checksum_range_text_input = s;
+                if let Ok(checksum) = self.checksum() {
+                    self.client_checksum = checksum;
+                }
+                Command::none()
+            },
+            SessionMessage::ClientChecksumAppendCheckbox(b) => {
+                self.client_checksum.append = b;
+                Command::none()
+            },
+            SessionMessage::ClientChecksumVerifyCheckbox(b) => {
+                self.client_checksum.verify = b;
+                Command::none()
+            },
+            SessionMessage::ClientFramingModePickList(mode) => {
+                let args = Framing{ mode, ..Framing::default() }.args();
+                self.set_framing(mode, args);
+                Command::none()
+            },
+            SessionMessage::ClientFramingTextInput(s) => {
+                self.set_framing(self.client_framing_mode, s);
+                Command::none()
+            },
+            SessionMessage::ClientLogPathTextInput(s) => {
+                self.client_log_path_text_input = s;
+                Command::none()
+            },
+            SessionMessage::ClientLogSaveButton => {
+                self.save_log();
+                Command::none()
+            },
+            SessionMessage::ClientLogClearButton => {
+                self.client_output.clear();
+                Command::none()
+            },
+            SessionMessage::ClientLogFormatPickList(format) => {
+                self.client_log_format = format;
+                Command::none()
+            },
+            SessionMessage::ClientLogMaxSizeTextInput(s) => {
+                self.client_log_max_size_text_input = s;
+                Command::none()
+            },
+            SessionMessage::ClientLogDailyCheckbox(b) => {
+                self.client_log_daily = b;
+                Command::none()
+            },
+            SessionMessage::ClientCapturePathTextInput(s) => {
+                self.client_capture_path_text_input = s;
+                Command::none()
+            },
+            SessionMessage::ClientCaptureExportButton => {
+                self.export_capture();
+                Command::none()
+            },
+            SessionMessage::ClientCaptureClearButton => {
+                self.capture.clear();
+                self.log("抓包已清空".to_string());
+                Command::none()
+            },
+            SessionMessage::ClientLogFileButton => {
+                if self.log_file.is_some() {
+                    self.log("停止记录日志".to_string());
+                    self.stop_log_file();
+                } else {
+                    self.start_log_file();
+                }
+                Command::none()
+            },
+            SessionMessage::Transport(generation, event) => {
+                let protocol = match self.transport {
+                    Some(ref config) if generation == self.transport_generation => config.protocol,
+                    _ => return Command::none()
+                };
+                match event {
+                    TransportEvent::Connected(handle, local_addr, remote_addr) => {
+                        match protocol {
+                            Protocol::TcpClient => self.log(format!("TCP连接:{} {}", remote_addr.ip(), remote_addr.port())),
+                            Protocol::TcpServer => self.log(format!("TCP监听:{} {}", local_addr.ip(), local_addr.port())),
+                            Protocol::UdpClient => self.log(format!("UDP连接:{} {} 本地:{} {}", remote_addr.ip(), remote_addr.port(),
+                                local_addr.ip(), local_addr.port())),
+                            Protocol::UdpServer => self.log(format!("UDP监听:{} {}", local_addr.ip(), local_addr.port())),
+                        }
+                        self.transport_handle = Some(handle);
+                        self.transport_local_addr = Some(local_addr);
+                        self.transport_retries = 0;
+                        if protocol == Protocol::TcpClient {
+                            let remote = self.transport.as_ref().map_or(remote_addr, |c| c.socket_addr);
+                            self.capture_event(remote, CaptureEvent::Open(true));
+                        }
+                        self.run_replay(Instant::now());
+                    },
+                    TransportEvent::PeerConnected(addr, local_addr) => {
+                        self.log(format!("TCP新连接:{} {}", addr.ip(), addr.port()));
+                        self.tcp_server_peers.push(addr);
+                        self.capture_local_addrs.insert(addr, local_addr);
+                        self.capture_event(addr, CaptureEvent::Open(false));
+                    },
+                    TransportEvent::PeerDisconnected(addr, e) => {
+                        self.flush_framer(addr);
+                        self.log(e.to_string());
+                        if protocol == Protocol::TcpServer {
+                            self.capture_event(addr, CaptureEvent::Close);
+                        }
+                        self.remove_peer(addr);
+                    },
+                    TransportEvent::Received(addr, buffer) => {
+                        return self.receive(protocol, addr, buffer);
+                    },
+                    TransportEvent::Error(e) => {
+                        self.log(e.to_string());
+                        let connected = self.transport_handle.is_some();
+                        match (protocol, &e) {
+                            (Protocol::UdpClient, RokitError::Refused(..))
+                            | (Protocol::UdpClient, RokitError::Reset(..))
+                            | (Protocol::UdpServer, RokitError::Refused(..))
+                            | (Protocol::UdpServer, RokitError::Reset(..))
+                            | (Protocol::UdpServer, RokitError::PeerClosed(..)) if connected => {},
+                            (Protocol::TcpClient, RokitError::Refused(..))
+                            | (Protocol::TcpClient, RokitError::Timeout(..)) if !connected && self.transport_retries < TCP_CONNECT_RETRY_MAX => {
+                                self.transport_retries += 1;
+                                self.log(format!("TCP重连({}/{})", self.transport_retries, TCP_CONNECT_RETRY_MAX));
+                                return Command::perform(async move {
+                                    smol::Timer::after(TCP_CONNECT_RETRY_DELAY).await;
+                                    generation
+                                }, SessionMessage::TransportRetry);
+                            },
+                            _ => self.close_all()
+                        }
+                    }
+                }
+                Command::none()
+            },
+            SessionMessage::TransportRetry(generation) => {
+                if self.transport.is_some() && generation == self.transport_generation {
+                    self.transport_generation += 1;
+                }
+                Command::none()
+            }
+        }
+    }
+
+    pub fn view(&mut self) -> Element<'_, SessionMessage> {
+
+        let peer_options = self.peer_options();
+        let repeat_progress = match self.repeat {
+            Some(ref repeat) => repeat.progress(),
+            None => String::new()
+        };
+        let replay_progress = match self.replay {
+            Some(ref replay) => replay.progress(),
+            None => String::new()
+        };
+        let resolved_options : Vec<String> = self.client_resolved_addrs.iter().map(|a| a.to_string()).collect();
+        let recent_options : Vec<String> = self.client_recent.iter().map(|p| p.label()).collect();
+
+        let client_text = Text::new("Socket客户端")
+            .font(FZFONT)
+            .vertical_alignment(VerticalAlignment::Center)
+            .horizontal_alignment(HorizontalAlignment::Center);
+
+        let client_ip_text_input = TextInput::new(&mut self.client_ip_text_input_state, "IP地址",&self.client_ip_text_input,SessionMessage::ClientIPTextInput)
+            .font(FZFONT)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let client_port_text_input = TextInput::new(&mut self.client_port_text_input_state, "端口",&self.client_port_text_input,SessionMessage::ClientPortTextInput)
+            .font(FZFONT)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let client_recent_pick_list = PickList::new(&mut self.client_recent_pick_list_state, recent_options, None, SessionMessage::ClientRecentPickList)
+            .font(FZFONT)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let client_tcp_button_text = Text::new(&self.client_tcp_button_text)
+            .font(FZFONT)
+            .size(15)
+            .vertical_alignment(VerticalAlignment::Center)
+            .horizontal_alignment(HorizontalAlignment::Center);
+        let client_tcp_button = Button::new(&mut self.client_tcp_button_state, client_tcp_button_text)
+            .on_press(SessionMessage::ClientTCPButton)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let client_udp_button_text = Text::new(&self.client_udp_button_text)
+            .font(FZFONT)
+            .size(15)
+            .vertical_alignment(VerticalAlignment::Center)
+            .horizontal_alignment(HorizontalAlignment::Center);
+        let client_udp_button = Button::new(&mut self.client_udp_button_state, client_udp_button_text)
+            .on_press(SessionMessage::ClientUDPButton)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let client_row = Row::new()
+            .push(client_ip_text_input)
+            .push(client_port_text_input)
+            .push(client_recent_pick_list)
+            .push(client_tcp_button)
+            .push(client_udp_button)
+            .padding(0)
+            .spacing(2)
+            .align_items(Align::Center)
+            .max_height(1000000)
+            .max_width(1000000);
+
+        let client_resolved_pick_list = PickList::new(&mut self.client_resolved_pick_list_state, resolved_options, self.client_resolved_selected.clone(), SessionMessage::ClientResolvedPickList)
+            .font(FZFONT)
+            .width(Length::FillPortion(3))
+            .padding(5);
+        let client_resolve_button_text = Text::new("解析")
+            .font(FZFONT)
+            .size(15)
+            .vertical_alignment(VerticalAlignment::Center)
+            .horizontal_alignment(HorizontalAlignment::Center);
+        let client_resolve_button = Button::new(&mut self.client_resolve_button_state, client_resolve_button_text)
+            .on_press(SessionMessage::ClientResolveButton)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let client_resolve_row = Row::new()
+            .push(client_resolved_pick_list)
+            .push(client_resolve_button)
+            .align_items(Align::Center)
+            .spacing(2);
+
+        let client_local_ip_text_input = TextInput::new(&mut self.client_local_ip_text_input_state, "本地IP",&self.client_local_ip_text_input,SessionMessage::ClientLocalIPTextInput)
+            .font(FZFONT)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let client_local_port_text_input = TextInput::new(&mut self.client_local_port_text_input_state, "本地端口",&self.client_local_port_text_input,SessionMessage::ClientLocalPortTextInput)
+            .font(FZFONT)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let client_buffer_size_text_input = TextInput::new(&mut self.client_buffer_size_text_input_state, "接收缓冲",&self.client_buffer_size_text_input,SessionMessage::ClientBufferSizeTextInput)
+            .font(FZFONT)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let client_local_row = Row::new()
+            .push(client_local_ip_text_input)
+            .push(client_local_port_text_input)
+            .push(client_buffer_size_text_input)
+            .align_items(Align::Center)
+            .spacing(2);
+
+        let client_tcp_server_button_text = Text::new(&self.client_tcp_server_button_text)
+            .font(FZFONT)
+            .size(15)
+            .vertical_alignment(VerticalAlignment::Center)
+            .horizontal_alignment(HorizontalAlignment::Center);
+        let client_tcp_server_button = Button::new(&mut self.client_tcp_server_button_state, client_tcp_server_button_text)
+            .on_press(SessionMessage::ClientTCPServerButton)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let client_udp_server_button_text = Text::new(&self.client_udp_server_button_text)
+            .font(FZFONT)
+            .size(15)
+            .vertical_alignment(VerticalAlignment::Center)
+            .horizontal_alignment(HorizontalAlignment::Center);
+        let client_udp_server_button = Button::new(&mut self.client_udp_server_button_state, client_udp_server_button_text)
+            .on_press(SessionMessage::ClientUDPServerButton)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let client_peer_pick_list = PickList::new(&mut self.client_peer_pick_list_state, peer_options, self.client_peer_selected.clone(), SessionMessage::ClientPeerPickList)
+            .font(FZFONT)
+            .width(Length::FillPortion(2))
+            .padding(5);
+        let client_server_row = Row::new()
+            .push(client_peer_pick_list)
+            .push(client_tcp_server_button)
+            .push(client_udp_server_button)
+            .align_items(Align::Center)
+            .spacing(2);
+
+        let client_buffer_text_input = TextInput::new(&mut self.client_buffer_text_input_state, "msg",&self.client_buffer_text_input,SessionMessage::ClientBufferTextInput)
+            .font(FZFONT)
+            .width(Length::FillPortion(3))
+            .padding(5);
+        let client_send_button_text = Text::new(&self.client_send_button_text)
+            .font(FZFONT)
+            .size(16)
+            .vertical_alignment(VerticalAlignment::Center)
+            .horizontal_alignment(HorizontalAlignment::Center);
+        let client_send_button = Button::new(&mut self.client_send_button_state, client_send_button_text)
+            .on_press(SessionMessage::ClientSendButton)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let client_buffer_row = Row::new()
+            .push(client_buffer_text_input)
+            .push(client_send_button)
+            .align_items(Align::Center)
+            .spacing(2);
+
+
+        let client_escape_checkbox = Checkbox::new(self.client_escape, "转义(\\r\\n \\xHH)", SessionMessage::ClientEscapeCheckbox)
+            .font(FZFONT)
+            .text_size(16)
+            .width(Length::FillPortion(1));
+        let client_line_ending_pick_list = PickList::new(&mut self.client_line_ending_pick_list_state, &LineEnding::ALL[..], Some(self.client_line_ending), SessionMessage::ClientLineEndingPickList)
+            .font(FZFONT)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let client_send_option_row = Row::new()
+            .push(client_escape_checkbox)
+            .push(client_line_ending_pick_list)
+            .align_items(Align::Center)
+            .spacing(2);
+
+        let client_ascii_buffer_text_input = TextInput::new(&mut self.client_ascii_buffer_text_input_state, "msg",&self.client_ascii_buffer_text_input,SessionMessage::ClientASCIIBufferTextInput)
+            .font(FZFONT)
+            .width(Length::FillPortion(3))
+            .padding(5);
+        let client_ascii_send_button_text = Text::new(&self.client_ascii_send_button_text)
+            .font(FZFONT)
+            .size(16)
+            .vertical_alignment(VerticalAlignment::Center)
+            .horizontal_alignment(HorizontalAlignment::Center);
+        let client_ascii_send_button = Button::new(&mut self.client_ascii_send_button_state, client_ascii_send_button_text)
+            .on_press(SessionMessage::ClientASCIISendButton)
+            .width(Length::FillPortion(1))
+            .padding(5);
+
+        let client_ascii_buffer_row = Row::new()
+            .push(client_ascii_buffer_text_input)
+            .push(client_ascii_send_button)
+            .align_items(Align::Center)
+            .spacing(2);
+
+        let client_hex_buffer_text_input = TextInput::new(&mut self.client_hex_buffer_text_input_state, "0A 1B ff",&self.client_hex_buffer_text_input,SessionMessage::ClientHexBufferTextInput)
+            .font(FZFONT)
+            .width(Length::FillPortion(3))
+            .padding(5);
+        let client_hex_send_button_text = Text::new(&self.client_hex_send_button_text)
+            .font(FZFONT)
+            .size(16)
+            .vertical_alignment(VerticalAlignment::Center)
+            .horizontal_alignment(HorizontalAlignment::Center);
+        let client_hex_send_button = Button::new(&mut self.client_hex_send_button_state, client_hex_send_button_text)
+            .on_press(SessionMessage::ClientHexSendButton)
+            .width(Length::FillPortion(1))
+            .padding(5);
+
+        let client_hex_buffer_row = Row::new()
+            .push(client_hex_buffer_text_input)
+            .push(client_hex_send_button)
+            .align_items(Align::Center)
+            .spacing(2);
+
+        let client_hex_display_checkbox = Checkbox::new(self.client_hex_display, "HEX显示", SessionMessage::ClientHexDisplayCheckbox)
+            .font(FZFONT)
+            .text_size(16)
+            .width(Length::FillPortion(1));
+        let client_encoding_pick_list = PickList::new(&mut self.client_encoding_pick_list_state, &TextEncoding::ALL[..], Some(self.client_encoding), SessionMessage::ClientEncodingPickList)
+            .font(FZFONT)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let client_display_row = Row::new()
+            .push(client_hex_display_checkbox)
+            .push(client_encoding_pick_list)
+            .align_items(Align::Center)
+            .spacing(2);
+
+        let client_checksum_pick_list = PickList::new(&mut self.client_checksum_pick_list_state, &ChecksumAlgorithm::ALL[..], Some(self.client_checksum.algorithm), SessionMessage::ClientChecksumPickList)
+            .font(FZFONT)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let client_checksum_range_text_input = TextInput::new(&mut self.client_checksum_range_text_input_state, "跳过开头:结尾",&self.client_checksum_range_text_input,SessionMessage::ClientChecksumRangeTextInput)
+            .font(FZFONT)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let client_checksum_append_checkbox = Checkbox::new(self.client_checksum.append, "追加", SessionMessage::ClientChecksumAppendCheckbox)
+            .font(FZFONT)
+            .text_size(16)
+            .width(Length::FillPortion(1));
+        let client_checksum_verify_checkbox = Checkbox::new(self.client_checksum.verify, "校验", SessionMessage::ClientChecksumVerifyCheckbox)
+            .font(FZFONT)
+            .text_size(16)
+            .width(Length::FillPortion(1));
+        let client_checksum_row = Row::new()
+            .push(client_checksum_pick_list)
+            .push(client_checksum_range_text_input)
+            .push(client_checksum_append_checkbox)
+            .push(client_checksum_verify_checkbox)
+            .align_items(Align::Center)
+            .spacing(2);
+
+        let client_framing_mode_pick_list = PickList::new(&mut self.client_framing_mode_pick_list_state, &FramingMode::ALL[..], Some(self.client_framing_mode), SessionMessage::ClientFramingModePickList)
+            .font(FZFONT)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let client_framing_placeholder = match self.client_framing_mode {
+            FramingMode::Raw => "",
+            FramingMode::Delimiter => "分隔符, 如\\r\\n \\x03",
+            FramingMode::FixedLength => "长度",
+            FramingMode::LengthPrefixed => "字节数:be|le:偏移:调整",
+        };
+        let client_framing_text_input = TextInput::new(&mut self.client_framing_text_input_state, client_framing_placeholder,&self.client_framing_text_input,SessionMessage::ClientFramingTextInput)
+            .font(FZFONT)
+            .width(Length::FillPortion(2))
+            .padding(5);
+        let client_framing_row = Row::new()
+            .push(client_framing_mode_pick_list)
+            .push(client_framing_text_input)
+            .align_items(Align::Center)
+            .spacing(2);
+
+        let client_repeat_interval_text_input = TextInput::new(&mut self.client_repeat_interval_text_input_state, "间隔(ms)",&self.client_repeat_interval_text_input,SessionMessage::ClientRepeatIntervalTextInput)
+            .font(FZFONT)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let client_repeat_count_text_input = TextInput::new(&mut self.client_repeat_count_text_input_state, "次数(空为无限)",&self.client_repeat_count_text_input,SessionMessage::ClientRepeatCountTextInput)
+            .font(FZFONT)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let client_repeat_jitter_text_input = TextInput::new(&mut self.client_repeat_jitter_text_input_state, "抖动(ms)",&self.client_repeat_jitter_text_input,SessionMessage::ClientRepeatJitterTextInput)
+            .font(FZFONT)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let client_repeat_row = Row::new()
+            .push(client_repeat_interval_text_input)
+            .push(client_repeat_count_text_input)
+            .push(client_repeat_jitter_text_input)
+            .align_items(Align::Center)
+            .spacing(2);
+
+        let client_repeat_source_pick_list = PickList::new(&mut self.client_repeat_source_pick_list_state, &RepeatSource::ALL[..], Some(self.client_repeat_source), SessionMessage::ClientRepeatSourcePickList)
+            .font(FZFONT)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let client_repeat_progress_text = Text::new(repeat_progress)
+            .font(FZFONT)
+            .size(16)
+            .width(Length::FillPortion(1))
+            .vertical_alignment(VerticalAlignment::Center)
+            .horizontal_alignment(HorizontalAlignment::Center);
+        let client_repeat_button_text = Text::new(&self.client_repeat_button_text)
+            .font(FZFONT)
+            .size(16)
+            .vertical_alignment(VerticalAlignment::Center)
+            .horizontal_alignment(HorizontalAlignment::Center);
+        let client_repeat_button = Button::new(&mut self.client_repeat_button_state, client_repeat_button_text)
+            .on_press(SessionMessage::ClientRepeatButton)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let client_repeat_control_row = Row::new()
+            .push(client_repeat_source_pick_list)
+            .push(client_repeat_progress_text)
+            .push(client_repeat_button)
+            .align_items(Align::Center)
+            .spacing(2);
+
+        let client_script_text_input = TextInput::new(&mut self.client_script_text_input_state, "脚本文件",&self.client_script_text_input,SessionMessage::ClientScriptTextInput)
+            .font(FZFONT)
+            .width(Length::FillPortion(3))
+            .padding(5);
+        let client_script_button_text = Text::new(&self.client_script_button_text)
+            .font(FZFONT)
+            .size(16)
+            .vertical_alignment(VerticalAlignment::Center)
+            .horizontal_alignment(HorizontalAlignment::Center);
+        let client_script_button = Button::new(&mut self.client_script_button_state, client_script_button_text)
+            .on_press(SessionMessage::ClientScriptButton)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let client_script_row = Row::new()
+            .push(client_script_text_input)
+            .push(client_script_button)
+            .align_items(Align::Center)
+            .spacing(2);
+
+        let client_replay_path_text_input = TextInput::new(&mut self.client_replay_path_text_input_state, "回放文件(日志/pcap)",&self.client_replay_path_text_input,SessionMessage::ClientReplayPathTextInput)
+            .font(FZFONT)
+            .width(Length::FillPortion(3))
+            .padding(5);
+        let client_replay_stream_text_input = TextInput::new(&mut self.client_replay_stream_text_input_state, "流序号",&self.client_replay_stream_text_input,SessionMessage::ClientReplayStreamTextInput)
+            .font(FZFONT)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let client_replay_row = Row::new()
+            .push(client_replay_path_text_input)
+            .push(client_replay_stream_text_input)
+            .align_items(Align::Center)
+            .spacing(2);
+
+        let client_replay_mode_pick_list = PickList::new(&mut self.client_replay_mode_pick_list_state, &ReplayMode::ALL[..], Some(self.client_replay_mode), SessionMessage::ClientReplayModePickList)
+            .font(FZFONT)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let client_replay_scale_text_input = TextInput::new(&mut self.client_replay_scale_text_input_state, "间隔倍率",&self.client_replay_scale_text_input,SessionMessage::ClientReplayScaleTextInput)
+            .font(FZFONT)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let client_replay_progress_text = Text::new(replay_progress)
+            .font(FZFONT)
+            .size(16)
+            .width(Length::FillPortion(1))
+            .vertical_alignment(VerticalAlignment::Center)
+            .horizontal_alignment(HorizontalAlignment::Center);
+        let client_replay_button_text = Text::new(&self.client_replay_button_text)
+            .font(FZFONT)
+            .size(16)
+            .vertical_alignment(VerticalAlignment::Center)
+            .horizontal_alignment(HorizontalAlignment::Center);
+        let client_replay_button = Button::new(&mut self.client_replay_button_state, client_replay_button_text)
+            .on_press(SessionMessage::ClientReplayButton)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let client_replay_control_row = Row::new()
+            .push(client_replay_mode_pick_list)
+            .push(client_replay_scale_text_input)
+            .push(client_replay_progress_text)
+            .push(client_replay_button)
+            .align_items(Align::Center)
+            .spacing(2);
+
+        let client_log_path_text_input = TextInput::new(&mut self.client_log_path_text_input_state, "日志文件",&self.client_log_path_text_input,SessionMessage::ClientLogPathTextInput)
+            .font(FZFONT)
+            .width(Length::FillPortion(2))
+            .padding(5);
+        let client_log_save_button_text = Text::new("保存日志")
+            .font(FZFONT)
+            .size(16)
+            .vertical_alignment(VerticalAlignment::Center)
+            .horizontal_alignment(HorizontalAlignment::Center);
+        let client_log_save_button = Button::new(&mut self.client_log_save_button_state, client_log_save_button_text)
+            .on_press(SessionMessage::ClientLogSaveButton)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let client_log_clear_button_text = Text::new("清空日志")
+            .font(FZFONT)
+            .size(16)
+            .vertical_alignment(VerticalAlignment::Center)
+            .horizontal_alignment(HorizontalAlignment::Center);
+        let client_log_clear_button = Button::new(&mut self.client_log_clear_button_state, client_log_clear_button_text)
+            .on_press(SessionMessage::ClientLogClearButton)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let client_log_row = Row::new()
+            .push(client_log_path_text_input)
+            .push(client_log_save_button)
+            .push(client_log_clear_button)
+            .align_items(Align::Center)
+            .spacing(2);
+
+        let client_log_format_pick_list = PickList::new(&mut self.client_log_format_pick_list_state, &LogFormat::ALL[..], Some(self.client_log_format), SessionMessage::ClientLogFormatPickList)
+            .font(FZFONT)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let client_log_max_size_text_input = TextInput::new(&mut self.client_log_max_size_text_input_state, "最大(MB)",&self.client_log_max_size_text_input,SessionMessage::ClientLogMaxSizeTextInput)
+            .font(FZFONT)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let client_log_daily_checkbox = Checkbox::new(self.client_log_daily, "按日期", SessionMessage::ClientLogDailyCheckbox)
+            .font(FZFONT)
+            .text_size(16)
+            .width(Length::FillPortion(1));
+        let client_log_file_button_text = Text::new(&self.client_log_file_button_text)
+            .font(FZFONT)
+            .size(16)
+            .vertical_alignment(VerticalAlignment::Center)
+            .horizontal_alignment(HorizontalAlignment::Center);
+        let client_log_file_button = Button::new(&mut self.client_log_file_button_state, client_log_file_button_text)
+            .on_press(SessionMessage::ClientLogFileButton)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let client_log_file_row = Row::new()
+            .push(client_log_format_pick_list)
+            .push(client_log_max_size_text_input)
+            .push(client_log_daily_checkbox)
+            .push(client_log_file_button)
+            .align_items(Align::Center)
+            .spacing(2);
+
+        let client_capture_path_text_input = TextInput::new(&mut self.client_capture_path_text_input_state, "抓包文件(.pcapng)",&self.client_capture_path_text_input,SessionMessage::ClientCapturePathTextInput)
+            .font(FZFONT)
+            .width(Length::FillPortion(2))
+            .padding(5);
+        let client_capture_export_button_text = Text::new(format!("导出({})", self.capture.len()))
+            .font(FZFONT)
+            .size(16)
+            .vertical_alignment(VerticalAlignment::Center)
+            .horizontal_alignment(HorizontalAlignment::Center);
+        let client_capture_export_button = Button::new(&mut self.client_capture_export_button_state, client_capture_export_button_text)
+            .on_press(SessionMessage::ClientCaptureExportButton)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let client_capture_clear_button_text = Text::new("清空抓包")
+            .font(FZFONT)
+            .size(16)
+            .vertical_alignment(VerticalAlignment::Center)
+            .horizontal_alignment(HorizontalAlignment::Center);
+        let client_capture_clear_button = Button::new(&mut self.client_capture_clear_button_state, client_capture_clear_button_text)
+            .on_press(SessionMessage::ClientCaptureClearButton)
+            .width(Length::FillPortion(1))
+            .padding(5);
+        let client_capture_row = Row::new()
+            .push(client_capture_path_text_input)
+            .push(client_capture_export_button)
+            .push(client_capture_clear_button)
+            .align_items(Align::Center)
+            .spacing(2);
+
+        let mut client_output_scrollable = Scrollable::new(&mut self.client_output_scrollable_state)
+            .max_height(275);
+        for (text, highlight) in self.client_output.iter() {
+            let mut client_output_text = Text::new(text.trim_end_matches('\n'))
+                .font(FZFONT)
+                .size(17)
+                .width(Length::Fill)
+                .vertical_alignment(VerticalAlignment::Top)
+                .horizontal_alignment(HorizontalAlignment::Left);
+            if *highlight {
+                client_output_text = client_output_text.color(CLIENT_OUTPUT_ALERT_COLOR);
+            }
+            client_output_scrollable = client_output_scrollable.push(client_output_text);
+        }
+
+        let client_column = Column::new()
+            .push(client_text)
+            .push(client_row)
+            .push(client_resolve_row)
+            .push(client_local_row)
+            .push(client_server_row)
+            .push(client_buffer_row)
+            .push(client_send_option_row)
+            .push(client_ascii_buffer_row)
+            .push(client_hex_buffer_row)
+            .push(client_display_row)
+            .push(client_framing_row)
+            .push(client_checksum_row)
+            .push(client_repeat_row)
+            .push(client_repeat_control_row)
+            .push(client_script_row)
+            .push(client_replay_row)
+            .push(client_replay_control_row)
+            .push(client_log_row)
+            .push(client_log_file_row)
+            .push(client_capture_row)
+            .push(self.modbus.view().map(SessionMessage::Modbus))
+            .push(client_output_scrollable)
+            .padding(16)
+            .spacing(12)
+            .align_items(Align::Start)
+            .width(Length::FillPortion(1))
+            .max_height(1000000)
+            .max_width(1000000);
+
+        client_column.into()
+    }
+}